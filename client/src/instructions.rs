@@ -0,0 +1,524 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{ed25519_program, instruction::Instruction, sysvar},
+    system_program, InstructionData, ToAccountMetas,
+};
+
+use anchor_escrow::{accounts, instruction};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address_with_program_id},
+    token_2022::spl_token_2022,
+};
+
+use crate::{find_bundle_address, find_config_address, find_delegate_address, find_escrow_address, find_event_authority_address,
+    find_order_nonce_address, find_metadata_address, find_price_feed_address, find_program_data_address, token_account, Bundle, BundleLeg, Escrow, SignedOrder, ID};
+
+// Instructions creating an Escrow take the maker and mints explicitly, the ones acting
+// on an existing Escrow take its deserialized state to derive every other account.
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn make(
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    token_program_a: &Pubkey,
+    token_program_b: &Pubkey,
+    args: instruction::Make,
+) -> Instruction {
+    let (escrow, _) = find_escrow_address(maker, args.seed);
+
+    build(
+        accounts::Make {
+            maker: *maker,
+            mint_a: *mint_a,
+            mint_b: *mint_b,
+            maker_ata_a: token_account(maker, mint_a, token_program_a),
+            escrow,
+            vault: token_account(&escrow, mint_a, token_program_a),
+            associated_token_program: associated_token::ID,
+            token_program_a: *token_program_a,
+            token_program_b: *token_program_b,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        args,
+    )
+}
+
+pub fn init_config(authority: &Pubkey, fee_recipient: &Pubkey, args: instruction::InitConfig) -> Instruction {
+    build(
+        accounts::InitConfig {
+            authority: *authority,
+            fee_recipient: *fee_recipient,
+            program_data: find_program_data_address().0,
+            config: find_config_address().0,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+pub fn update_config(authority: &Pubkey, fee_recipient: &Pubkey, args: instruction::UpdateConfig) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            authority: *authority,
+            fee_recipient: *fee_recipient,
+            config: find_config_address().0,
+        },
+        args,
+    )
+}
+
+pub fn update(escrow: &Escrow, args: instruction::Update) -> Instruction {
+    build(
+        accounts::Update {
+            maker: escrow.maker,
+            escrow: find_escrow_address(&escrow.maker, escrow.seed).0,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        args,
+    )
+}
+
+pub fn deposit_more(escrow: &Escrow, token_program_a: &Pubkey, args: instruction::DepositMore) -> Instruction {
+    let (escrow_key, _) = find_escrow_address(&escrow.maker, escrow.seed);
+
+    build(
+        accounts::DepositMore {
+            maker: escrow.maker,
+            mint_a: escrow.mint_a,
+            maker_ata_a: token_account(&escrow.maker, &escrow.mint_a, token_program_a),
+            escrow: escrow_key,
+            vault: token_account(&escrow_key, &escrow.mint_a, token_program_a),
+            token_program_a: *token_program_a,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        args,
+    )
+}
+
+pub fn withdraw_some(escrow: &Escrow, token_program_a: &Pubkey, args: instruction::WithdrawSome) -> Instruction {
+    let (escrow_key, _) = find_escrow_address(&escrow.maker, escrow.seed);
+
+    build(
+        accounts::WithdrawSome {
+            maker: escrow.maker,
+            mint_a: escrow.mint_a,
+            maker_ata_a: token_account(&escrow.maker, &escrow.mint_a, token_program_a),
+            escrow: escrow_key,
+            vault: token_account(&escrow_key, &escrow.mint_a, token_program_a),
+            associated_token_program: associated_token::ID,
+            token_program_a: *token_program_a,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        args,
+    )
+}
+
+pub fn refund(escrow: &Escrow, token_program_a: &Pubkey) -> Instruction {
+    let (escrow_key, _) = find_escrow_address(&escrow.maker, escrow.seed);
+
+    build(
+        accounts::Refund {
+            maker: escrow.maker,
+            mint_a: escrow.mint_a,
+            maker_ata_a: token_account(&escrow.maker, &escrow.mint_a, token_program_a),
+            escrow: escrow_key,
+            vault: token_account(&escrow_key, &escrow.mint_a, token_program_a),
+            associated_token_program: associated_token::ID,
+            token_program_a: *token_program_a,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        instruction::Refund {},
+    )
+}
+
+pub fn close_expired(cranker: &Pubkey, escrow: &Escrow, token_program_a: &Pubkey) -> Instruction {
+    let (escrow_key, _) = find_escrow_address(&escrow.maker, escrow.seed);
+
+    build(
+        accounts::CloseExpired {
+            cranker: *cranker,
+            maker: escrow.maker,
+            mint_a: escrow.mint_a,
+            maker_ata_a: token_account(&escrow.maker, &escrow.mint_a, token_program_a),
+            escrow: escrow_key,
+            vault: token_account(&escrow_key, &escrow.mint_a, token_program_a),
+            associated_token_program: associated_token::ID,
+            token_program_a: *token_program_a,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        instruction::CloseExpired {},
+    )
+}
+
+fn take_accounts(
+    taker: &Pubkey,
+    escrow: &Escrow,
+    mint_b: &Pubkey,
+    fee_recipient: &Pubkey,
+    referrer: Option<&Pubkey>,
+    token_program_a: &Pubkey,
+    token_program_b: &Pubkey,
+) -> accounts::Take {
+    let (escrow_key, _) = find_escrow_address(&escrow.maker, escrow.seed);
+
+    accounts::Take {
+        taker: *taker,
+        maker: escrow.maker,
+        mint_a: escrow.mint_a,
+        mint_b: *mint_b,
+        taker_ata_a: token_account(taker, &escrow.mint_a, token_program_a),
+        taker_ata_b: token_account(taker, mint_b, token_program_b),
+        maker_ata_b: token_account(&escrow.maker, mint_b, token_program_b),
+        config: find_config_address().0,
+        fee_recipient: *fee_recipient,
+        fee_recipient_ata_b: token_account(fee_recipient, mint_b, token_program_b),
+        referrer: referrer.copied(),
+        referrer_ata_b: referrer.and_then(|referrer| token_account(referrer, mint_b, token_program_b)),
+        escrow: escrow_key,
+        vault: token_account(&escrow_key, &escrow.mint_a, token_program_a),
+        metadata_b: escrow.nft.collection_b.then(|| find_metadata_address(mint_b).0),
+        price_feed: escrow.oracle.map(|oracle| find_price_feed_address(&oracle.feed_id, 0).0),
+        associated_token_program: associated_token::ID,
+        token_program_a: *token_program_a,
+        token_program_b: *token_program_b,
+        system_program: system_program::ID,
+        event_authority: find_event_authority_address().0,
+        program: ID,
+    }
+}
+
+/// `fee_recipient` must match the one stored in the global `Config` account.
+pub fn take(
+    taker: &Pubkey,
+    escrow: &Escrow,
+    fee_recipient: &Pubkey,
+    referrer: Option<&Pubkey>,
+    token_program_a: &Pubkey,
+    token_program_b: &Pubkey,
+    args: instruction::Take,
+) -> Instruction {
+    build(take_accounts(taker, escrow, &escrow.mint_b, fee_recipient, referrer, token_program_a, token_program_b), args)
+}
+
+pub fn take_partial(
+    taker: &Pubkey,
+    escrow: &Escrow,
+    fee_recipient: &Pubkey,
+    referrer: Option<&Pubkey>,
+    token_program_a: &Pubkey,
+    token_program_b: &Pubkey,
+    args: instruction::TakePartial,
+) -> Instruction {
+    build(take_accounts(taker, escrow, &escrow.mint_b, fee_recipient, referrer, token_program_a, token_program_b), args)
+}
+
+/// Fills every Escrow of `escrows` whole; they must all trade the same mints.
+pub fn take_many(
+    taker: &Pubkey,
+    escrows: &[Escrow],
+    fee_recipient: &Pubkey,
+    token_program_a: &Pubkey,
+    token_program_b: &Pubkey,
+    args: instruction::TakeMany,
+) -> Option<Instruction> {
+    let first = escrows.first()?;
+    let ata = |owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey| {
+        get_associated_token_address_with_program_id(owner, mint, token_program)
+    };
+
+    let mut instruction = build(
+        accounts::TakeMany {
+            taker: *taker,
+            mint_a: first.mint_a,
+            mint_b: first.mint_b,
+            taker_ata_a: ata(taker, &first.mint_a, token_program_a),
+            taker_ata_b: ata(taker, &first.mint_b, token_program_b),
+            config: find_config_address().0,
+            fee_recipient: *fee_recipient,
+            fee_recipient_ata_b: ata(fee_recipient, &first.mint_b, token_program_b),
+            associated_token_program: associated_token::ID,
+            token_program_a: *token_program_a,
+            token_program_b: *token_program_b,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        args,
+    );
+
+    instruction.accounts.extend(escrows.iter().flat_map(|escrow| {
+        let (escrow_key, _) = find_escrow_address(&escrow.maker, escrow.seed);
+        [
+            AccountMeta::new(escrow_key, false),
+            AccountMeta::new(ata(&escrow_key, &escrow.mint_a, token_program_a), false),
+            AccountMeta::new(escrow.maker, false),
+            AccountMeta::new(ata(&escrow.maker, &escrow.mint_b, token_program_b), false),
+        ]
+    }));
+    Some(instruction)
+}
+
+/// Fills one unit of a collection bid with `nft`, an NFT of the collection stored as
+/// the Escrow's mint B.
+#[allow(clippy::too_many_arguments)]
+pub fn take_collection_bid(
+    taker: &Pubkey,
+    escrow: &Escrow,
+    nft: &Pubkey,
+    fee_recipient: &Pubkey,
+    referrer: Option<&Pubkey>,
+    token_program_a: &Pubkey,
+    token_program_b: &Pubkey,
+    args: instruction::TakePartial,
+) -> Instruction {
+    build(take_accounts(taker, escrow, nft, fee_recipient, referrer, token_program_a, token_program_b), args)
+}
+
+/// Bids on an English auction Escrow, refunding the current top bidder if any.
+pub fn bid(bidder: &Pubkey, escrow: &Escrow, token_program_b: &Pubkey, args: instruction::Bid) -> Instruction {
+    let (escrow_key, _) = find_escrow_address(&escrow.maker, escrow.seed);
+    let previous_bidder = escrow.english.and_then(|auction| auction.top_bidder);
+
+    build(
+        accounts::Bid {
+            bidder: *bidder,
+            maker: escrow.maker,
+            mint_b: escrow.mint_b,
+            bidder_ata_b: get_associated_token_address_with_program_id(bidder, &escrow.mint_b, token_program_b),
+            previous_bidder,
+            previous_bidder_ata_b: previous_bidder
+                .map(|previous_bidder| get_associated_token_address_with_program_id(&previous_bidder, &escrow.mint_b, token_program_b)),
+            escrow: escrow_key,
+            bid_vault: get_associated_token_address_with_program_id(&escrow_key, &escrow.mint_b, token_program_b),
+            associated_token_program: associated_token::ID,
+            token_program_b: *token_program_b,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        args,
+    )
+}
+
+/// Settles an expired English auction with a bid. Returns `None` when there is no top bidder.
+pub fn settle_auction(
+    payer: &Pubkey,
+    escrow: &Escrow,
+    fee_recipient: &Pubkey,
+    token_program_a: &Pubkey,
+    token_program_b: &Pubkey,
+) -> Option<Instruction> {
+    let (escrow_key, _) = find_escrow_address(&escrow.maker, escrow.seed);
+    let auction = escrow.english?;
+    let winner = auction.top_bidder?;
+
+    Some(build(
+        accounts::SettleAuction {
+            payer: *payer,
+            maker: escrow.maker,
+            winner,
+            first_bidder: auction.first_bidder?,
+            mint_a: escrow.mint_a,
+            mint_b: escrow.mint_b,
+            winner_ata_a: get_associated_token_address_with_program_id(&winner, &escrow.mint_a, token_program_a),
+            maker_ata_b: get_associated_token_address_with_program_id(&escrow.maker, &escrow.mint_b, token_program_b),
+            config: find_config_address().0,
+            fee_recipient: *fee_recipient,
+            fee_recipient_ata_b: get_associated_token_address_with_program_id(fee_recipient, &escrow.mint_b, token_program_b),
+            escrow: escrow_key,
+            vault: get_associated_token_address_with_program_id(&escrow_key, &escrow.mint_a, token_program_a),
+            bid_vault: get_associated_token_address_with_program_id(&escrow_key, &escrow.mint_b, token_program_b),
+            associated_token_program: associated_token::ID,
+            token_program_a: *token_program_a,
+            token_program_b: *token_program_b,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        instruction::SettleAuction {},
+    ))
+}
+
+/// Lets signed orders pull up to `amount` of the maker's mint A. A token account has
+/// a single delegate, so this replaces any earlier approval.
+pub fn approve_delegate(maker: &Pubkey, mint_a: &Pubkey, token_program_a: &Pubkey, amount: u64, decimals: u8) -> Instruction {
+    spl_token_2022::instruction::approve_checked(
+        token_program_a,
+        &get_associated_token_address_with_program_id(maker, mint_a, token_program_a),
+        mint_a,
+        &find_delegate_address().0,
+        maker,
+        &[],
+        amount,
+        decimals,
+    )
+    .unwrap()
+}
+
+/// The Ed25519 program instruction checking the maker's `signature` of the order,
+/// followed by `fill_signed_order`; both go in the same transaction, in this order.
+pub fn fill_signed_order(
+    taker: &Pubkey,
+    order: SignedOrder,
+    signature: &[u8; 64],
+    fee_recipient: &Pubkey,
+    token_program_a: &Pubkey,
+    token_program_b: &Pubkey,
+) -> [Instruction; 2] {
+    let verify = Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data: anchor_escrow::ed25519::instruction_data(&order.maker, signature, &order.message()),
+    };
+
+    let fill = build(
+        accounts::FillSignedOrder {
+            taker: *taker,
+            maker: order.maker,
+            mint_a: order.mint_a,
+            mint_b: order.mint_b,
+            maker_ata_a: get_associated_token_address_with_program_id(&order.maker, &order.mint_a, token_program_a),
+            taker_ata_a: get_associated_token_address_with_program_id(taker, &order.mint_a, token_program_a),
+            taker_ata_b: get_associated_token_address_with_program_id(taker, &order.mint_b, token_program_b),
+            maker_ata_b: get_associated_token_address_with_program_id(&order.maker, &order.mint_b, token_program_b),
+            config: find_config_address().0,
+            fee_recipient: *fee_recipient,
+            fee_recipient_ata_b: get_associated_token_address_with_program_id(fee_recipient, &order.mint_b, token_program_b),
+            order_nonce: find_order_nonce_address(&order.maker).0,
+            delegate: find_delegate_address().0,
+            instructions: sysvar::instructions::ID,
+            associated_token_program: associated_token::ID,
+            token_program_a: *token_program_a,
+            token_program_b: *token_program_b,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        instruction::FillSignedOrder { order },
+    );
+
+    [verify, fill]
+}
+
+pub fn cancel_orders_up_to(maker: &Pubkey, args: instruction::CancelOrdersUpTo) -> Instruction {
+    build(
+        accounts::CancelOrders {
+            maker: *maker,
+            order_nonce: find_order_nonce_address(maker).0,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        args,
+    )
+}
+
+// Bundle instructions pass every leg as `[mint, source, destination, token program]`
+// remaining accounts; `token_programs` lists the token program of each leg in order.
+
+fn bundle_leg_accounts(
+    legs: &[BundleLeg],
+    token_programs: &[Pubkey],
+    source_owner: &Pubkey,
+    destination_owner: &Pubkey,
+) -> Vec<AccountMeta> {
+    legs.iter()
+        .zip(token_programs)
+        .flat_map(|(leg, token_program)| {
+            [
+                AccountMeta::new(leg.mint, false),
+                AccountMeta::new(get_associated_token_address_with_program_id(source_owner, &leg.mint, token_program), false),
+                AccountMeta::new(get_associated_token_address_with_program_id(destination_owner, &leg.mint, token_program), false),
+                AccountMeta::new_readonly(*token_program, false),
+            ]
+        })
+        .collect()
+}
+
+pub fn make_bundle(maker: &Pubkey, token_programs: &[Pubkey], args: instruction::MakeBundle) -> Instruction {
+    let (bundle, _) = find_bundle_address(maker, args.seed);
+    let legs = bundle_leg_accounts(&args.give, token_programs, maker, &bundle);
+
+    let mut instruction = build(
+        accounts::MakeBundle {
+            maker: *maker,
+            bundle,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        args,
+    );
+    instruction.accounts.extend(legs);
+    instruction
+}
+
+/// `give_token_programs` and `receive_token_programs` follow the legs of each side.
+pub fn take_bundle(
+    taker: &Pubkey,
+    bundle: &Bundle,
+    fee_recipient: &Pubkey,
+    give_token_programs: &[Pubkey],
+    receive_token_programs: &[Pubkey],
+) -> Instruction {
+    let (bundle_key, _) = find_bundle_address(&bundle.maker, bundle.seed);
+
+    let mut instruction = build(
+        accounts::TakeBundle {
+            taker: *taker,
+            maker: bundle.maker,
+            bundle: bundle_key,
+            config: find_config_address().0,
+            fee_recipient: *fee_recipient,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        instruction::TakeBundle {},
+    );
+    instruction.accounts.extend(bundle_leg_accounts(&bundle.give, give_token_programs, &bundle_key, taker));
+    instruction.accounts.extend(bundle_leg_accounts(&bundle.receive, receive_token_programs, taker, &bundle.maker));
+    instruction.accounts.extend(bundle.receive.iter().zip(receive_token_programs).map(|(leg, token_program)| {
+        AccountMeta::new(get_associated_token_address_with_program_id(fee_recipient, &leg.mint, token_program), false)
+    }));
+    instruction
+}
+
+pub fn refund_bundle(bundle: &Bundle, token_programs: &[Pubkey]) -> Instruction {
+    let (bundle_key, _) = find_bundle_address(&bundle.maker, bundle.seed);
+
+    let mut instruction = build(
+        accounts::RefundBundle {
+            maker: bundle.maker,
+            bundle: bundle_key,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: find_event_authority_address().0,
+            program: ID,
+        },
+        instruction::RefundBundle {},
+    );
+    instruction.accounts.extend(bundle_leg_accounts(&bundle.give, token_programs, &bundle_key, &bundle.maker));
+    instruction
+}
@@ -0,0 +1,108 @@
+use anchor_lang::{
+    event::EVENT_IX_TAG_LE,
+    prelude::*,
+    solana_program::{bpf_loader_upgradeable, instruction::AccountMeta},
+    AccountDeserialize, Event,
+};
+
+use anchor_spl::{associated_token::get_associated_token_address_with_program_id, metadata::mpl_token_metadata, token, token_2022};
+
+use anchor_escrow::oracle::PYTH_PUSH_ORACLE_ID;
+
+pub use anchor_escrow::{self, Bundle, BundleLeg, Config, Escrow, Expiry, ExpiryMode, OrderNonce, SignedOrder,
+    TimeSpec, ID, NATIVE_MINT};
+
+pub mod instructions;
+pub use instructions::*;
+
+pub fn find_escrow_address(maker: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", maker.as_ref(), seed.to_le_bytes().as_ref()], &ID)
+}
+
+pub fn find_bundle_address(maker: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"bundle", maker.as_ref(), seed.to_le_bytes().as_ref()], &ID)
+}
+
+pub fn find_config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], &ID)
+}
+
+/// Signer of the self-invocations through which the program emits its events.
+pub fn find_event_authority_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"__event_authority"], &ID)
+}
+
+/// ProgramData account of the escrow program, whose upgrade authority is the only
+/// one allowed to initialize the Config.
+pub fn find_program_data_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID)
+}
+
+/// Account makers approve as delegate of their mint A tokens so signed orders can be filled.
+pub fn find_delegate_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"delegate"], &ID)
+}
+
+/// Replay protection account of a maker's signed orders.
+pub fn find_order_nonce_address(maker: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"nonce", maker.as_ref()], &ID)
+}
+
+/// Metaplex metadata account of `mint`, passed to `take` for collection-restricted NFT escrows.
+pub fn find_metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()], &mpl_token_metadata::ID)
+}
+
+/// Price update account of the Pyth feed `feed_id` in shard `shard_id` of the push
+/// oracle. Sponsored feeds are kept in shard 0.
+pub fn find_price_feed_address(feed_id: &[u8; 32], shard_id: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[&shard_id.to_le_bytes(), feed_id], &PYTH_PUSH_ORACLE_ID)
+}
+
+/// Associated token account of `owner` for `mint`, or `None` for the wrapped SOL
+/// mint, whose legs are settled in native lamports.
+pub fn token_account(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Option<Pubkey> {
+    (*mint != NATIVE_MINT).then(|| get_associated_token_address_with_program_id(owner, mint, token_program))
+}
+
+/// Token program to pass for a mint, given the owner of the mint account. Returns
+/// `None` when the account is not owned by SPL Token or Token-2022.
+pub fn token_program_for_mint(mint_owner: &Pubkey) -> Option<Pubkey> {
+    [token::ID, token_2022::ID].into_iter().find(|program| program == mint_owner)
+}
+
+/// Accounts to append to an instruction moving a mint with a transfer hook: the hook
+/// program, its validation account and the extra accounts it lists, already resolved.
+pub fn transfer_hook_accounts(mint: &Pubkey, hook_program: &Pubkey, extra_accounts: &[AccountMeta]) -> Vec<AccountMeta> {
+    let (validation, _) = Pubkey::find_program_address(&[b"extra-account-metas", mint.as_ref()], hook_program);
+
+    [AccountMeta::new_readonly(*hook_program, false), AccountMeta::new_readonly(validation, false)]
+        .into_iter()
+        .chain(extra_accounts.iter().cloned())
+        .collect()
+}
+
+pub fn deserialize_escrow(mut data: &[u8]) -> Result<Escrow> {
+    Escrow::try_deserialize(&mut data)
+}
+
+pub fn deserialize_bundle(mut data: &[u8]) -> Result<Bundle> {
+    Bundle::try_deserialize(&mut data)
+}
+
+pub fn deserialize_config(mut data: &[u8]) -> Result<Config> {
+    Config::try_deserialize(&mut data)
+}
+
+pub fn deserialize_order_nonce(mut data: &[u8]) -> Result<OrderNonce> {
+    OrderNonce::try_deserialize(&mut data)
+}
+
+/// Decodes an event of type `T` from the data of an inner instruction, where the
+/// program invokes itself with each event it emits. Returns `None` for any other
+/// instruction or event.
+pub fn decode_event<T: Event>(instruction_data: &[u8]) -> Option<T> {
+    let data = instruction_data.strip_prefix(EVENT_IX_TAG_LE.as_slice())?;
+    let data = data.strip_prefix(T::DISCRIMINATOR.as_slice())?;
+    T::try_from_slice(data).ok()
+}
@@ -0,0 +1,252 @@
+use {
+    anchor_escrow_client::{anchor_escrow::{instruction, state::OracleTerms, EscrowMade, EscrowTaken}, *},
+    anchor_lang::{event::EVENT_IX_TAG_LE, AccountSerialize, Discriminator, Event},
+    solana_sdk::pubkey::Pubkey,
+};
+
+fn escrow(mint_a: Pubkey) -> Escrow {
+    let maker = Pubkey::new_unique();
+    let seed = 42;
+
+    Escrow {
+        seed,
+        maker,
+        mint_a,
+        mint_b: Pubkey::new_unique(),
+        amount: 100,
+        receive: 300,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump: find_escrow_address(&maker, seed).1,
+    }
+}
+
+#[test]
+fn escrow_round_trip() {
+    let escrow = escrow(Pubkey::new_unique());
+
+    let mut data = vec![];
+    escrow.try_serialize(&mut data).unwrap();
+
+    let decoded = deserialize_escrow(&data).unwrap();
+    assert_eq!(decoded.maker, escrow.maker);
+    assert_eq!(decoded.amount, escrow.amount);
+    assert!(deserialize_config(&data).is_err(), "An Escrow should not decode as a Config");
+}
+
+#[test]
+fn event_round_trip() {
+    let event = EscrowMade {
+        escrow: Pubkey::new_unique(),
+        seed: 42,
+        maker: Pubkey::new_unique(),
+        mint_a: Pubkey::new_unique(),
+        mint_b: Pubkey::new_unique(),
+        mint_a_flags: 3,
+        deposit: 100,
+        receive: 300,
+        start_at: 10,
+        expiry: Expiry::Timestamp(3_610),
+        timestamp: 10,
+    };
+
+    // What emit_cpi! passes to the program's self-invocation
+    let data = [EVENT_IX_TAG_LE.as_slice(), &event.data()].concat();
+
+    let decoded = decode_event::<EscrowMade>(&data).unwrap();
+    assert_eq!(decoded.escrow, event.escrow);
+    assert_eq!(decoded.mint_a_flags, 3);
+    assert_eq!(decoded.receive, 300);
+    assert_eq!(decoded.expiry, Expiry::Timestamp(3_610));
+    assert!(decode_event::<EscrowTaken>(&data).is_none(), "An EscrowMade should not decode as an EscrowTaken");
+    assert!(decode_event::<EscrowMade>(&event.data()).is_none(), "Data without the event tag should not decode");
+}
+
+#[test]
+fn token_program_selection() {
+    assert_eq!(token_program_for_mint(&anchor_spl::token::ID), Some(anchor_spl::token::ID));
+    assert_eq!(token_program_for_mint(&anchor_spl::token_2022::ID), Some(anchor_spl::token_2022::ID));
+    assert_eq!(token_program_for_mint(&Pubkey::new_unique()), None);
+}
+
+#[test]
+fn make_derives_escrow_and_vault() {
+    let maker = Pubkey::new_unique();
+    let mint_a = Pubkey::new_unique();
+    let mint_b = Pubkey::new_unique();
+    let token_program = anchor_spl::token_2022::ID;
+
+    let ix = make(&maker, &mint_a, &mint_b, &token_program, &token_program, instruction::Make {
+        seed: 7,
+        deposit: 100,
+        receive: 300,
+        net_receive: false,
+        expiry: ExpiryMode::Timestamp(TimeSpec::After(3_600)),
+        taker: None,
+        allowlist: None,
+        allowed_mint_flags: 0,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        start_at: None,
+    });
+
+    let (escrow, _) = find_escrow_address(&maker, 7);
+    assert_eq!(ix.program_id, ID);
+    assert_eq!(&ix.data[..8], &instruction::Make::DISCRIMINATOR);
+    assert_eq!(ix.accounts[4].pubkey, escrow);
+    assert_eq!(ix.accounts[5].pubkey, token_account(&escrow, &mint_a, &token_program).unwrap());
+}
+
+#[test]
+fn native_legs_skip_token_accounts() {
+    let escrow = escrow(NATIVE_MINT);
+    let ix = refund(&escrow, &anchor_spl::token::ID);
+
+    // Optional accounts left out are replaced by the program id
+    assert_eq!(ix.accounts[2].pubkey, ID, "Maker ATA should be left out");
+    assert_eq!(ix.accounts[4].pubkey, ID, "Vault should be left out");
+}
+
+#[test]
+fn take_with_referrer() {
+    let escrow = escrow(Pubkey::new_unique());
+    let taker = Pubkey::new_unique();
+    let fee_recipient = Pubkey::new_unique();
+    let referrer = Pubkey::new_unique();
+    let token_program = anchor_spl::token::ID;
+
+    let ix = take(&taker, &escrow, &fee_recipient, Some(&referrer), &token_program, &token_program, instruction::Take {
+        min_amount_a: 100,
+        max_amount_b: 300,
+        proof: vec![],
+    });
+
+    assert_eq!(ix.accounts[7].pubkey, find_config_address().0);
+    assert_eq!(ix.accounts[10].pubkey, referrer);
+    assert_eq!(ix.accounts[11].pubkey, token_account(&referrer, &escrow.mint_b, &token_program).unwrap());
+}
+
+#[test]
+fn take_many_appends_escrow_accounts() {
+    let mint_a = Pubkey::new_unique();
+    let first = escrow(mint_a);
+    let second = Escrow { maker: Pubkey::new_unique(), mint_b: first.mint_b, ..escrow(mint_a) };
+    let taker = Pubkey::new_unique();
+    let token_program = anchor_spl::token::ID;
+
+    let ix = take_many(&taker, &[first, second.clone()], &Pubkey::new_unique(), &token_program, &token_program, instruction::TakeMany {
+        min_amount_a: 200,
+        max_amount_b: 600,
+    })
+    .unwrap();
+
+    let (second_key, _) = find_escrow_address(&second.maker, second.seed);
+    let escrows = &ix.accounts[14..];
+    assert_eq!(escrows.len(), 8);
+    assert_eq!(escrows[4].pubkey, second_key);
+    assert_eq!(escrows[5].pubkey, token_account(&second_key, &mint_a, &token_program).unwrap());
+    assert_eq!(escrows[6].pubkey, second.maker);
+    assert_eq!(escrows[7].pubkey, token_account(&second.maker, &second.mint_b, &token_program).unwrap());
+}
+
+#[test]
+fn take_collection_bid_passes_nft_and_metadata() {
+    let mut escrow = escrow(Pubkey::new_unique());
+    escrow.nft.nft_b = true;
+    escrow.nft.collection_b = true;
+    let taker = Pubkey::new_unique();
+    let nft = Pubkey::new_unique();
+    let token_program = anchor_spl::token::ID;
+
+    let ix = take_collection_bid(&taker, &escrow, &nft, &Pubkey::new_unique(), None, &token_program, &token_program, instruction::TakePartial {
+        amount_b: 1,
+        min_amount_a: 0,
+        max_amount_b: 1,
+        proof: vec![],
+    });
+
+    assert_eq!(ix.accounts[3].pubkey, nft);
+    assert_eq!(ix.accounts[6].pubkey, token_account(&escrow.maker, &nft, &token_program).unwrap());
+    assert_eq!(ix.accounts[14].pubkey, find_metadata_address(&nft).0);
+}
+
+#[test]
+fn take_oracle_escrow_passes_push_feed() {
+    let mut escrow = escrow(Pubkey::new_unique());
+    let feed_id = [7; 32];
+    escrow.oracle = Some(OracleTerms { feed_id, spread_bps: 0, max_age: 60, max_conf_bps: 100 });
+    let token_program = anchor_spl::token::ID;
+
+    let ix = take(&Pubkey::new_unique(), &escrow, &Pubkey::new_unique(), None, &token_program, &token_program, instruction::Take {
+        min_amount_a: 0,
+        max_amount_b: u64::MAX,
+        proof: vec![],
+    });
+
+    let (feed, _) = Pubkey::find_program_address(&[&0u16.to_le_bytes(), &feed_id], &anchor_escrow::oracle::PYTH_PUSH_ORACLE_ID);
+    assert_eq!(ix.accounts[15].pubkey, feed);
+}
+
+#[test]
+fn take_bundle_lists_legs_then_fee_accounts() {
+    let maker = Pubkey::new_unique();
+    let taker = Pubkey::new_unique();
+    let token_program = anchor_spl::token::ID;
+    let bundle = Bundle {
+        seed: 9,
+        maker,
+        give: vec![BundleLeg { mint: Pubkey::new_unique(), amount: 10 }],
+        receive: vec![BundleLeg { mint: Pubkey::new_unique(), amount: 20 }, BundleLeg { mint: Pubkey::new_unique(), amount: 30 }],
+        start_at: 0,
+        expiry: Expiry::Never,
+        bump: find_bundle_address(&maker, 9).1,
+    };
+
+    let fee_recipient = Pubkey::new_unique();
+    let ix = take_bundle(&taker, &bundle, &fee_recipient, &[token_program], &[token_program, token_program]);
+
+    let (bundle_key, _) = find_bundle_address(&maker, 9);
+    let legs = &ix.accounts[9..];
+    assert_eq!(legs.len(), 14);
+    assert_eq!(legs[0].pubkey, bundle.give[0].mint);
+    assert_eq!(legs[1].pubkey, token_account(&bundle_key, &bundle.give[0].mint, &token_program).unwrap());
+    assert_eq!(legs[2].pubkey, token_account(&taker, &bundle.give[0].mint, &token_program).unwrap());
+    assert_eq!(legs[8].pubkey, bundle.receive[1].mint);
+    assert_eq!(legs[10].pubkey, token_account(&maker, &bundle.receive[1].mint, &token_program).unwrap());
+    assert_eq!(legs[13].pubkey, token_account(&fee_recipient, &bundle.receive[1].mint, &token_program).unwrap());
+}
+
+#[test]
+fn fill_signed_order_verifies_signature_first() {
+    let taker = Pubkey::new_unique();
+    let token_program = anchor_spl::token::ID;
+    let order = SignedOrder {
+        maker: Pubkey::new_unique(),
+        mint_a: Pubkey::new_unique(),
+        mint_b: Pubkey::new_unique(),
+        amount_a: 100,
+        amount_b: 300,
+        expiry: i64::MAX,
+        taker: None,
+        nonce: 1,
+    };
+
+    let [verify, fill] = fill_signed_order(&taker, order, &[7; 64], &Pubkey::new_unique(), &token_program, &token_program);
+
+    let message = order.message();
+    assert_eq!(verify.program_id, solana_sdk::ed25519_program::ID);
+    assert_eq!(&verify.data[16..48], order.maker.as_ref());
+    assert!(verify.data.ends_with(&message));
+    assert_eq!(fill.accounts[4].pubkey, token_account(&order.maker, &order.mint_a, &token_program).unwrap());
+    assert_eq!(fill.accounts[11].pubkey, find_order_nonce_address(&order.maker).0);
+    assert_eq!(fill.accounts[12].pubkey, find_delegate_address().0);
+}
@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address_with_program_id, Create},
+    token,
+    token_2022::{
+        self,
+        spl_token_2022::{
+            extension::{
+                transfer_fee::TransferFeeConfig, transfer_hook::TransferHook, BaseStateWithExtensions,
+                StateWithExtensions,
+            },
+            state::{Account, Mint},
+        },
+    },
+    token_interface::{close_account, transfer_checked, CloseAccount, TransferChecked},
+};
+
+use crate::{BundleLeg, EscrowErrors};
+
+// Every leg of a bundle instruction is passed through the remaining accounts as
+// `[mint, source, destination, token program]`, in the order of the legs stored
+// on the Bundle. Legs move exact amounts without extra accounts, so mints with a
+// transfer fee or a transfer hook can't be traded in a Bundle.
+
+pub const LEG_ACCOUNTS: usize = 4;
+
+pub struct LegAccounts<'a, 'info> {
+    pub leg: BundleLeg,
+    pub mint: &'a AccountInfo<'info>,
+    pub source: &'a AccountInfo<'info>,
+    pub destination: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+}
+
+/// Splits `accounts` into the accounts of each leg, checking the mint of the leg,
+/// that its token program is the one owning the mint and that the mint is supported.
+pub fn split<'a, 'info>(accounts: &'a [AccountInfo<'info>], legs: &[BundleLeg]) -> Result<Vec<LegAccounts<'a, 'info>>> {
+    require_eq!(accounts.len(), legs.len() * LEG_ACCOUNTS, EscrowErrors::BundleAccountMismatch);
+
+    accounts
+        .chunks_exact(LEG_ACCOUNTS)
+        .zip(legs)
+        .map(|(accounts, leg)| {
+            let [mint, source, destination, token_program] = accounts else {
+                unreachable!()
+            };
+
+            require_keys_eq!(mint.key(), leg.mint, EscrowErrors::BundleAccountMismatch);
+            require!(
+                [token::ID, token_2022::ID].contains(token_program.key) && mint.owner == token_program.key,
+                EscrowErrors::BundleAccountMismatch
+            );
+            require!(!has_transfer_fee_or_hook(mint)?, EscrowErrors::UnsupportedBundleMint);
+
+            Ok(LegAccounts { leg: *leg, mint, source, destination, token_program })
+        })
+        .collect()
+}
+
+/// Whether `mint` has a transfer fee or a transfer hook.
+fn has_transfer_fee_or_hook(mint: &AccountInfo) -> Result<bool> {
+    let data = mint.try_borrow_data()?;
+
+    // SPL Token mints unpack as a Token-2022 mint without extensions
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    Ok(mint.get_extension::<TransferFeeConfig>().is_ok() || mint.get_extension::<TransferHook>().is_ok())
+}
+
+impl<'a, 'info> LegAccounts<'a, 'info> {
+    fn ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, self.mint.key, self.token_program.key)
+    }
+
+    /// Checks that the source is the associated token account of `owner`.
+    pub fn check_source(&self, owner: &Pubkey) -> Result<()> {
+        require_keys_eq!(self.source.key(), self.ata(owner), EscrowErrors::BundleAccountMismatch);
+        Ok(())
+    }
+
+    /// Checks that the destination is the associated token account of `owner`, and
+    /// creates it if needed.
+    pub fn init_destination(
+        &self,
+        owner: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        associated_token_program: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        require_keys_eq!(self.destination.key(), self.ata(owner.key), EscrowErrors::BundleAccountMismatch);
+
+        let accounts = Create {
+            payer: payer.clone(),
+            associated_token: self.destination.clone(),
+            authority: owner.clone(),
+            mint: self.mint.clone(),
+            system_program: system_program.clone(),
+            token_program: self.token_program.clone(),
+        };
+
+        create_idempotent(CpiContext::new(associated_token_program.clone(), accounts))
+    }
+
+    /// Balance of the source token account.
+    pub fn source_amount(&self) -> Result<u64> {
+        let data = self.source.try_borrow_data()?;
+        Ok(StateWithExtensions::<Account>::unpack(&data)?.base.amount)
+    }
+
+    pub fn transfer(&self, authority: &AccountInfo<'info>, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let decimals = StateWithExtensions::<Mint>::unpack(&self.mint.try_borrow_data()?)?.base.decimals;
+
+        let accounts = TransferChecked {
+            from: self.source.clone(),
+            mint: self.mint.clone(),
+            to: self.destination.clone(),
+            authority: authority.clone(),
+        };
+
+        let ctx = CpiContext::new_with_signer(self.token_program.clone(), accounts, signer_seeds);
+
+        transfer_checked(ctx, amount, decimals)
+    }
+
+    /// Empties the source vault into the destination and closes it, sending its rent
+    /// to `rent_destination`. Returns the amount released.
+    pub fn sweep_source(
+        &self,
+        authority: &AccountInfo<'info>,
+        rent_destination: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<u64> {
+        let amount = self.source_amount()?;
+        self.transfer(authority, amount, signer_seeds)?;
+
+        let accounts = CloseAccount {
+            account: self.source.clone(),
+            destination: rent_destination.clone(),
+            authority: authority.clone(),
+        };
+
+        close_account(CpiContext::new_with_signer(self.token_program.clone(), accounts, signer_seeds))?;
+
+        Ok(amount)
+    }
+}
@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{transfer_fee, transfer_hook::transfer_checked, BidPlaced, Escrow, EscrowErrors, Expiry};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Bid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mint::token_program = token_program_b,
+    )]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program_b,
+    )]
+    pub bidder_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub previous_bidder: Option<SystemAccount<'info>>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        associated_token::mint = mint_b,
+        associated_token::authority = previous_bidder,
+        associated_token::token_program = token_program_b,
+    )]
+    pub previous_bidder_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_b,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        associated_token::mint = mint_b,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_b,
+    )]
+    pub bid_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Bid<'info> {
+    /// Locks `amount` of mint B in the bid vault as the new top bid of an English
+    /// auction and refunds the bid it replaces.
+    pub fn bid(&mut self, amount: u64, proof: &[[u8; 32]], remaining_accounts: &[AccountInfo<'info>]) -> Result<BidPlaced> {
+        let mut auction = self.escrow.english.ok_or(EscrowErrors::NotAnAuction)?;
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
+
+        require_gte!(now, self.escrow.start_at, EscrowErrors::EscrowNotStarted);
+        require!(!self.escrow.expiry.has_passed(&clock), EscrowErrors::EscrowExpired);
+        require!(self.escrow.is_allowed(self.bidder.key, proof), EscrowErrors::TakerNotAllowed);
+
+        // Bids are compared on what reaches the bid vault
+        let net_amount = amount - transfer_fee::fee(&self.mint_b.to_account_info(), amount)?;
+        require_gte!(net_amount, auction.min_bid(self.escrow.receive)?, EscrowErrors::BidTooLow);
+
+        let accounts = TransferChecked {
+            from: self.bidder_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: self.bid_vault.to_account_info(),
+            authority: self.bidder.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(self.token_program_b.to_account_info(), accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(ctx, amount, self.mint_b.decimals)?;
+
+        let previous_bidder = auction.top_bidder;
+        if let Some(top_bidder) = previous_bidder {
+            self.refund_bid(top_bidder, auction.top_bid, remaining_accounts)?;
+        }
+
+        // A late bid pushes expiry back so other bidders get a chance to answer
+        let Expiry::Timestamp(mut expiry) = self.escrow.expiry else {
+            return err!(EscrowErrors::InvalidAuction);
+        };
+        if expiry - now < auction.terms.extension {
+            expiry = now + auction.terms.extension;
+            self.escrow.expiry = Expiry::Timestamp(expiry);
+        }
+
+        // The first bid creates the bid vault
+        auction.first_bidder.get_or_insert(self.bidder.key());
+        auction.top_bidder = Some(self.bidder.key());
+        auction.top_bid = net_amount;
+        self.escrow.english = Some(auction);
+
+        Ok(BidPlaced {
+            escrow: self.escrow.key(),
+            bidder: self.bidder.key(),
+            previous_bidder,
+            amount: net_amount,
+            expiry,
+            timestamp: now,
+        })
+    }
+
+    /// Sends the outbid top bid back to its bidder.
+    fn refund_bid(&self, top_bidder: Pubkey, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let previous_bidder = self.previous_bidder.as_ref().ok_or(EscrowErrors::BidderMismatch)?;
+        require_keys_eq!(previous_bidder.key(), top_bidder, EscrowErrors::BidderMismatch);
+
+        let previous_bidder_ata_b =
+            self.previous_bidder_ata_b.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let accounts = TransferChecked {
+            from: self.bid_vault.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: previous_bidder_ata_b.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program_b.to_account_info(),
+            accounts,
+            &signer_seeds,
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(ctx, amount, self.mint_b.decimals)
+    }
+}
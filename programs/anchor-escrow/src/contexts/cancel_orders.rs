@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{EscrowErrors, OrderNonce, OrdersCancelled};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelOrders<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = maker,
+        space = 8 + OrderNonce::INIT_SPACE,
+        seeds = [b"nonce", maker.key().as_ref()],
+        bump
+    )]
+    pub order_nonce: Account<'info, OrderNonce>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelOrders<'info> {
+    /// Cancels every signed order of the maker with a nonce up to `nonce` included.
+    pub fn cancel_orders_up_to(&mut self, nonce: u64, bumps: &CancelOrdersBumps) -> Result<OrdersCancelled> {
+        let min_nonce = nonce.checked_add(1).ok_or(EscrowErrors::MathOverflow)?;
+        require_gt!(min_nonce, self.order_nonce.min_nonce, EscrowErrors::InvalidNonce);
+
+        self.order_nonce.maker = self.maker.key();
+        self.order_nonce.bump = bumps.order_nonce;
+        self.order_nonce.advance(min_nonce);
+
+        Ok(OrdersCancelled {
+            maker: self.maker.key(),
+            min_nonce,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{drain_vault, Escrow, EscrowErrors, EscrowRefunded};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseExpired<'info> {
+    #[account(mut)]
+    cranker: Signer<'info>,
+    #[account(mut)]
+    maker: SystemAccount<'info>,
+    #[account(mut)]
+    mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = mint_a,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    token_program_a: Interface<'info, TokenInterface>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> CloseExpired<'info> {
+    /// Returns mint A to the maker once the Escrow has expired. The rent of the vault
+    /// goes to the cranker as a bounty, the rent of the Escrow account to the maker.
+    pub fn close_expired(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<EscrowRefunded> {
+        require!(self.escrow.expiry.has_passed(&Clock::get()?), EscrowErrors::EscrowNotExpired);
+        require!(!self.escrow.has_bids(), EscrowErrors::AuctionHasBids);
+
+        drain_vault(
+            &self.escrow,
+            &self.mint_a,
+            self.maker_ata_a.as_ref(),
+            self.vault.as_ref(),
+            &self.token_program_a,
+            self.cranker.to_account_info(),
+            remaining_accounts,
+        )
+    }
+}
@@ -0,0 +1,98 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::{transfer_fee, transfer_hook::transfer_checked, Escrow, EscrowErrors, EscrowResized};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositMore<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositMore<'info> {
+    pub fn deposit_more(
+        &mut self,
+        amount: u64,
+        scale_receive: bool,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<EscrowResized> {
+        require!(amount > 0, EscrowErrors::InvalidAmount);
+
+        let available = self.escrow.amount;
+
+        // Native SOL is held by the Escrow account itself
+        if self.escrow.is_native_a() {
+            let transfer_accounts = Transfer {
+                from: self.maker.to_account_info(),
+                to: self.escrow.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+
+            transfer(cpi_ctx, amount)?;
+        } else {
+            let maker_ata_a = self.maker_ata_a.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+            let vault = self.vault.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+
+            let transfer_accounts = TransferChecked {
+                from: maker_ata_a.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: vault.to_account_info(),
+                authority: self.maker.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+
+            transfer_checked(cpi_ctx, amount, self.mint_a.decimals)?;
+        }
+
+        let received = amount - transfer_fee::fee(&self.mint_a.to_account_info(), amount)?;
+        let amount_a = available.checked_add(received).ok_or(EscrowErrors::MathOverflow)?;
+
+        self.escrow.amount = amount_a;
+
+        if scale_receive {
+            self.escrow.rescale(available, amount_a)?;
+        }
+
+        Ok(EscrowResized {
+            escrow: self.escrow.key(),
+            seed: self.escrow.seed,
+            maker: self.maker.key(),
+            amount_a,
+            receive: self.escrow.receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
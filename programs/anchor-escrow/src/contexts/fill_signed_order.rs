@@ -0,0 +1,175 @@
+use anchor_lang::{prelude::*, solana_program::sysvar};
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    ed25519, transfer_hook::transfer_checked, Config, EscrowErrors, OrderNonce, SignedOrder,
+    SignedOrderFilled,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order: SignedOrder)]
+pub struct FillSignedOrder<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(address = order.maker)]
+    pub maker: SystemAccount<'info>,
+    #[account(
+        address = order.mint_a,
+        mint::token_program = token_program_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        address = order.mint_b,
+        mint::token_program = token_program_b,
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a,
+    )]
+    pub maker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a,
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b,
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b,
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    pub fee_recipient: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = token_program_b,
+    )]
+    pub fee_recipient_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        space = 8 + OrderNonce::INIT_SPACE,
+        seeds = [b"nonce", maker.key().as_ref()],
+        bump
+    )]
+    pub order_nonce: Box<Account<'info, OrderNonce>>,
+    /// CHECK: Signing-only PDA that makers approve as delegate of their mint A tokens
+    #[account(
+        seeds = [b"delegate"],
+        bump,
+    )]
+    pub delegate: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, read to find the Ed25519 signature check
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FillSignedOrder<'info> {
+    /// Fills an order the maker signed off-chain. The transaction must verify the
+    /// maker's signature with the Ed25519 program in the instruction right before.
+    pub fn fill_signed_order(
+        &mut self,
+        order: &SignedOrder,
+        bumps: &FillSignedOrderBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<SignedOrderFilled> {
+        ed25519::verify(&self.instructions, &order.maker, &order.message())?;
+
+        // The first fill of a maker creates its OrderNonce
+        self.order_nonce.maker = self.maker.key();
+        self.order_nonce.bump = bumps.order_nonce;
+        self.order_nonce.use_nonce(order.nonce)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        require_gte!(order.expiry, now, EscrowErrors::EscrowExpired);
+        require!(order.amount_a > 0 && order.amount_b > 0, EscrowErrors::InvalidAmount);
+        if let Some(taker) = order.taker {
+            require_keys_eq!(taker, self.taker.key(), EscrowErrors::TakerNotAllowed);
+        }
+
+        let (protocol_fee, _) = self.config.split_fee(order.amount_b, false)?;
+
+        self.pay(&self.maker_ata_b, order.amount_b - protocol_fee, remaining_accounts)?;
+
+        if protocol_fee > 0 {
+            self.pay(&self.fee_recipient_ata_b, protocol_fee, remaining_accounts)?;
+        }
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[b"delegate", &[bumps.delegate]]];
+
+        let accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: self.delegate.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), accounts, &signer_seeds)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(ctx, order.amount_a, self.mint_a.decimals)?;
+
+        Ok(SignedOrderFilled {
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            amount_a: order.amount_a,
+            amount_b: order.amount_b,
+            protocol_fee,
+            nonce: order.nonce,
+            timestamp: now,
+        })
+    }
+
+    fn pay(
+        &self,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(self.token_program_b.to_account_info(), accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(ctx, amount, self.mint_b.decimals)
+    }
+}
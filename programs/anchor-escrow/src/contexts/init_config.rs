@@ -0,0 +1,45 @@
+use anchor_lang::{prelude::*, solana_program::bpf_loader_upgradeable};
+
+use crate::{Config, EscrowErrors};
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub fee_recipient: SystemAccount<'info>,
+    /// The program's own ProgramData, so that only its upgrade authority can set
+    /// the Config up.
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        seeds::program = bpf_loader_upgradeable::ID,
+        bump,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ EscrowErrors::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitConfig<'info> {
+    pub fn init_config(&mut self, fee_bps: u16, referral_bps: u16, bumps: &InitConfigBumps) -> Result<()> {
+        require!(fee_bps <= Config::MAX_FEE_BPS && referral_bps <= Config::MAX_BPS, EscrowErrors::InvalidFee);
+
+        self.config.set_inner(
+            Config {
+                authority: self.authority.key(),
+                fee_recipient: self.fee_recipient.key(),
+                fee_bps,
+                referral_bps,
+                bump: bumps.config,
+            }
+        );
+        Ok(())
+    }
+}
@@ -1,32 +1,36 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
-use crate::Escrow;
+use crate::{mint_policy, nft, transfer_fee, transfer_hook::transfer_checked, DutchAuction, EnglishAuction, EnglishTerms, Escrow, EscrowErrors, EscrowMade, Expiry, ExpiryMode, NftTerms, OracleTerms, TimeSpec, NATIVE_MINT};
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(seed: u64)]
 pub struct Make<'info> {
     #[account(mut)]
     pub maker: Signer<'info>,
     #[account(
-        mint::token_program = token_program
+        mint::token_program = token_program_a
     )]
     pub mint_a: InterfaceAccount<'info, Mint>,
     #[account(
-        mint::token_program = token_program
+        mint::token_program = token_program_b
     )]
     pub mint_b: InterfaceAccount<'info, Mint>,
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
-    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init,
         payer = maker,
@@ -40,39 +44,156 @@ pub struct Make<'info> {
         payer = maker,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
-    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_program: Interface<'info, TokenInterface>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Make<'info> {
-    pub fn save_escrow(&mut self, seed: u64, receive: u64, expiry: u64, bumps: &MakeBumps) -> Result<()> {
+    /// Rejects mint A if it has risky features the maker did not allow.
+    pub fn check_mint_a(&self, allowed_mint_flags: u8) -> Result<()> {
+        mint_policy::check(mint_policy::flags(&self.mint_a.to_account_info())?, allowed_mint_flags)
+    }
+
+    pub fn check_nft(&self, nft: &NftTerms, deposit: u64, receive: u64) -> Result<()> {
+        require!(nft.nft_b || !nft.collection_b, EscrowErrors::InvalidNftTerms);
+
+        if nft.nft_a {
+            require!(nft::is_nft(&self.mint_a), EscrowErrors::NotAnNft);
+            require_eq!(deposit, 1, EscrowErrors::InvalidNftTerms);
+        }
+
+        if nft.nft_b {
+            require!(nft::is_nft(&self.mint_b), EscrowErrors::NotAnNft);
+            // A collection bid can ask for several NFTs, each filling one unit of receive
+            require!(receive == 1 || nft.collection_b, EscrowErrors::InvalidNftTerms);
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn save_escrow(
+        &mut self,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        net_receive: bool,
+        expiry: ExpiryMode,
+        taker: Option<Pubkey>,
+        allowlist: Option<[u8; 32]>,
+        nft: NftTerms,
+        auction: Option<DutchAuction>,
+        english: Option<EnglishTerms>,
+        oracle: Option<OracleTerms>,
+        start_at: Option<TimeSpec>,
+        bumps: &MakeBumps,
+    ) -> Result<EscrowMade> {
+        require!(receive > 0 && deposit > 0, EscrowErrors::InvalidAmount);
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        let expiry = expiry.resolve(timestamp)?;
+
+        if let Some(terms) = english {
+            // Bids are held in a bid vault and can't be combined with another pricing.
+            // The auction needs an end time for late bids to push back.
+            require!(
+                auction.is_none()
+                    && !nft.nft_b
+                    && self.mint_a.key() != NATIVE_MINT
+                    && self.mint_b.key() != NATIVE_MINT
+                    && terms.extension >= 0
+                    && matches!(expiry, Expiry::Timestamp(_)),
+                EscrowErrors::InvalidAuction
+            );
+        }
+
+        if let Some(auction) = auction {
+            // NFT prices count whole NFTs and can't decay
+            require!(!nft.nft_b, EscrowErrors::InvalidAuction);
+            auction.check(receive)?;
+        }
+
+        if let Some(oracle) = oracle {
+            // The feed prices fungible amounts and replaces any other pricing
+            require!(
+                auction.is_none() && english.is_none() && !nft.nft_b,
+                EscrowErrors::InvalidOracleTerms
+            );
+            oracle.check()?;
+        }
+
+        // Staged Escrows become fillable at `start_at`, which must come before expiry
+        let start_at = TimeSpec::resolve_start(start_at, expiry, timestamp)?;
+
+        // Record what actually reached the vault after mint A transfer fees
+        let amount = deposit - transfer_fee::fee(&self.mint_a.to_account_info(), deposit)?;
+
         self.escrow.set_inner(
             Escrow {
                 seed,
                 maker: self.maker.key(),
                 mint_a: self.mint_a.key(),
                 mint_b: self.mint_b.key(),
+                amount,
                 receive,
-                expiry: Clock::get()?.unix_timestamp.checked_add(expiry as i64).unwrap(),
+                net_receive,
+                start_at,
+                expiry,
+                taker,
+                allowlist,
+                nft,
+                auction,
+                english: english.map(|terms| EnglishAuction { terms, first_bidder: None, top_bidder: None, top_bid: 0 }),
+                oracle,
                 bump: bumps.escrow,
             }
         );
-        Ok(())
+
+        Ok(EscrowMade {
+            escrow: self.escrow.key(),
+            seed,
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            mint_a_flags: mint_policy::flags(&self.mint_a.to_account_info())?,
+            deposit: amount,
+            receive,
+            start_at,
+            expiry: self.escrow.expiry,
+            timestamp,
+        })
     }
 
-    pub fn deposit(&mut self, deposit: u64) -> Result<()> {
+    pub fn deposit(&mut self, deposit: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Native SOL is held by the Escrow account itself
+        if self.mint_a.key() == NATIVE_MINT {
+            let transfer_accounts = Transfer {
+                from: self.maker.to_account_info(),
+                to: self.escrow.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+
+            return transfer(cpi_ctx, deposit);
+        }
+
+        let maker_ata_a = self.maker_ata_a.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+        let vault = self.vault.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+
         let transfer_accounts = TransferChecked {
-            from: self.maker_ata_a.to_account_info(),
+            from: maker_ata_a.to_account_info(),
             mint: self.mint_a.to_account_info(),
-            to: self.vault.to_account_info(),
+            to: vault.to_account_info(),
             authority: self.maker.to_account_info(),
         };
 
-        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
 
         transfer_checked(cpi_ctx, deposit, self.mint_a.decimals)
     }
@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::{bundle, Bundle, BundleLeg, BundleMade, ExpiryMode, TimeSpec};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBundle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + Bundle::INIT_SPACE,
+        seeds = [b"bundle", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bundle: Account<'info, Bundle>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeBundle<'info> {
+    pub fn save_bundle(
+        &mut self,
+        seed: u64,
+        give: Vec<BundleLeg>,
+        receive: Vec<BundleLeg>,
+        expiry: ExpiryMode,
+        start_at: Option<TimeSpec>,
+        bumps: &MakeBundleBumps,
+    ) -> Result<BundleMade> {
+        Bundle::check_legs(&give)?;
+        Bundle::check_legs(&receive)?;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        let expiry = expiry.resolve(timestamp)?;
+        let start_at = TimeSpec::resolve_start(start_at, expiry, timestamp)?;
+
+        let event = BundleMade {
+            bundle: self.bundle.key(),
+            seed,
+            maker: self.maker.key(),
+            give: give.clone(),
+            receive: receive.clone(),
+            start_at,
+            expiry,
+            timestamp,
+        };
+
+        self.bundle.set_inner(Bundle {
+            seed,
+            maker: self.maker.key(),
+            give,
+            receive,
+            start_at,
+            expiry,
+            bump: bumps.bundle,
+        });
+
+        Ok(event)
+    }
+
+    /// Moves every `give` leg from the maker into a vault owned by the Bundle.
+    pub fn deposit(&self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let maker = self.maker.to_account_info();
+
+        for leg in bundle::split(remaining_accounts, &self.bundle.give)? {
+            leg.init_destination(
+                &self.bundle.to_account_info(),
+                &maker,
+                &self.associated_token_program.to_account_info(),
+                &self.system_program.to_account_info(),
+            )?;
+            leg.transfer(&maker, leg.leg.amount, &[])?;
+        }
+
+        Ok(())
+    }
+}
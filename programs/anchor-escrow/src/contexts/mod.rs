@@ -1,3 +1,9 @@
+pub mod init_config;
+pub use init_config::*;
+
+pub mod update_config;
+pub use update_config::*;
+
 pub mod make;
 pub use make::*;
 
@@ -6,3 +12,39 @@ pub use refund::*;
 
 pub mod take;
 pub use take::*;
+
+pub mod take_many;
+pub use take_many::*;
+
+pub mod update;
+pub use update::*;
+
+pub mod deposit_more;
+pub use deposit_more::*;
+
+pub mod withdraw_some;
+pub use withdraw_some::*;
+
+pub mod close_expired;
+pub use close_expired::*;
+
+pub mod bid;
+pub use bid::*;
+
+pub mod settle_auction;
+pub use settle_auction::*;
+
+pub mod fill_signed_order;
+pub use fill_signed_order::*;
+
+pub mod cancel_orders;
+pub use cancel_orders::*;
+
+pub mod make_bundle;
+pub use make_bundle::*;
+
+pub mod take_bundle;
+pub use take_bundle::*;
+
+pub mod refund_bundle;
+pub use refund_bundle::*;
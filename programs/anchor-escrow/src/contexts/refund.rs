@@ -3,26 +3,27 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
-        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
-        TransferChecked,
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
     },
 };
 
-use crate::Escrow;
+use crate::{transfer_fee, transfer_hook::transfer_checked, Escrow, EscrowErrors, EscrowRefunded};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Refund<'info> {
     #[account(mut)]
     maker: Signer<'info>,
+    #[account(mut)]
     mint_a: InterfaceAccount<'info, Mint>,
     #[account(
         init_if_needed,
         payer = maker,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
-    maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         close = maker,
@@ -36,50 +37,102 @@ pub struct Refund<'info> {
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_a
     )]
-    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
     associated_token_program: Program<'info, AssociatedToken>,
-    token_program: Interface<'info, TokenInterface>,
+    token_program_a: Interface<'info, TokenInterface>,
     system_program: Program<'info, System>,
 }
 
 impl<'info> Refund<'info> {
-    pub fn refund_and_close_vault(&mut self) -> Result<()> {
+    pub fn refund_and_close_vault(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<EscrowRefunded> {
+        require!(!self.escrow.has_bids(), EscrowErrors::AuctionHasBids);
+
+        drain_vault(
+            &self.escrow,
+            &self.mint_a,
+            self.maker_ata_a.as_ref(),
+            self.vault.as_ref(),
+            &self.token_program_a,
+            self.maker.to_account_info(),
+            remaining_accounts,
+        )
+    }
+}
+
+/// Returns mint A to the maker ahead of closing the Escrow: the vault is emptied into
+/// the maker's token account and closed, its rent going to `rent_destination`.
+pub fn drain_vault<'info>(
+    escrow: &Account<'info, Escrow>,
+    mint_a: &InterfaceAccount<'info, Mint>,
+    maker_ata_a: Option<&InterfaceAccount<'info, TokenAccount>>,
+    vault: Option<&InterfaceAccount<'info, TokenAccount>>,
+    token_program_a: &Interface<'info, TokenInterface>,
+    rent_destination: AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<EscrowRefunded> {
+    // Native SOL goes back to the maker when the Escrow account is closed
+    let amount_a = if escrow.is_native_a() {
+        Escrow::native_balance(&escrow.to_account_info())?
+    } else {
+        let maker_ata_a = maker_ata_a.ok_or(EscrowErrors::TokenAccountRequired)?;
+        let vault = vault.ok_or(EscrowErrors::TokenAccountRequired)?;
+
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
-            self.maker.to_account_info().key.as_ref(),
-            &self.escrow.seed.to_le_bytes()[..],
-            &[self.escrow.bump],
+            escrow.maker.as_ref(),
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump],
         ]];
 
         let xfer_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
+            from: vault.to_account_info(),
+            mint: mint_a.to_account_info(),
+            to: maker_ata_a.to_account_info(),
+            authority: escrow.to_account_info(),
         };
 
         let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
+            token_program_a.to_account_info(),
             xfer_accounts,
             &signer_seeds,
-        );
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(ctx, vault.amount, mint_a.decimals)?;
 
-        transfer_checked(ctx, self.vault.amount, self.mint_a.decimals)?;
+        transfer_fee::harvest(
+            token_program_a.to_account_info(),
+            mint_a.to_account_info(),
+            vault.to_account_info(),
+        )?;
 
         let close_accounts = CloseAccount {
-            account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
-            authority: self.escrow.to_account_info(),
+            account: vault.to_account_info(),
+            destination: rent_destination,
+            authority: escrow.to_account_info(),
         };
 
         let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
+            token_program_a.to_account_info(),
             close_accounts,
             &signer_seeds,
         );
 
-        close_account(ctx)
-    }
+        close_account(ctx)?;
+
+        vault.amount
+    };
+
+    Ok(EscrowRefunded {
+        escrow: escrow.key(),
+        seed: escrow.seed,
+        maker: escrow.maker,
+        mint_a: mint_a.key(),
+        mint_b: escrow.mint_b,
+        amount_a,
+        expiry: escrow.expiry,
+        timestamp: Clock::get()?.unix_timestamp,
+    })
 }
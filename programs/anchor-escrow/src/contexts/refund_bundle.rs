@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::{bundle, Bundle, BundleRefunded};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefundBundle<'info> {
+    #[account(mut)]
+    maker: Signer<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"bundle", maker.key().as_ref(), bundle.seed.to_le_bytes().as_ref()],
+        bump = bundle.bump
+    )]
+    bundle: Account<'info, Bundle>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> RefundBundle<'info> {
+    /// Empties and closes every vault of the Bundle back to the maker.
+    pub fn refund_bundle(&self, remaining_accounts: &[AccountInfo<'info>]) -> Result<BundleRefunded> {
+        let maker = self.maker.to_account_info();
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bundle",
+            self.maker.to_account_info().key.as_ref(),
+            &self.bundle.seed.to_le_bytes()[..],
+            &[self.bundle.bump],
+        ]];
+
+        for leg in bundle::split(remaining_accounts, &self.bundle.give)? {
+            leg.check_source(&self.bundle.key())?;
+            leg.init_destination(
+                &maker,
+                &maker,
+                &self.associated_token_program.to_account_info(),
+                &self.system_program.to_account_info(),
+            )?;
+            leg.sweep_source(&self.bundle.to_account_info(), &maker, &signer_seeds)?;
+        }
+
+        Ok(BundleRefunded {
+            bundle: self.bundle.key(),
+            seed: self.bundle.seed,
+            maker: self.maker.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
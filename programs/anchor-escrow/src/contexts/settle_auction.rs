@@ -0,0 +1,225 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    transfer_fee, transfer_hook::transfer_checked, AuctionSettled, Config, Escrow, EscrowErrors, FeesCollected,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(mut)]
+    pub winner: SystemAccount<'info>,
+    #[account(mut)]
+    pub first_bidder: SystemAccount<'info>,
+    #[account(
+        mut,
+        mint::token_program = token_program_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        mint::token_program = token_program_b,
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = winner,
+        associated_token::token_program = token_program_a,
+    )]
+    pub winner_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b,
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    pub fee_recipient: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = token_program_b,
+    )]
+    pub fee_recipient_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = mint_a,
+        has_one = mint_b,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_a,
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_b,
+    )]
+    pub bid_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleAuction<'info> {
+    /// Completes an English auction once it has expired: mint A goes to the top
+    /// bidder and the top bid, less the protocol fee, to the maker. Anyone can settle.
+    pub fn settle_auction(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<(Option<FeesCollected>, AuctionSettled)> {
+        let auction = self.escrow.english.ok_or(EscrowErrors::NotAnAuction)?;
+        let winner = auction.top_bidder.ok_or(EscrowErrors::NoBids)?;
+
+        require!(self.escrow.expiry.has_passed(&Clock::get()?), EscrowErrors::EscrowNotExpired);
+        require_keys_eq!(self.winner.key(), winner, EscrowErrors::BidderMismatch);
+        require_keys_eq!(
+            self.first_bidder.key(),
+            auction.first_bidder.ok_or(EscrowErrors::NoBids)?,
+            EscrowErrors::BidderMismatch
+        );
+
+        let (protocol_fee, _) = self.config.split_fee(auction.top_bid, false)?;
+
+        // The vault may hold more than the offered amount; the winner gets all of it
+        self.transfer_out(
+            &self.vault,
+            &self.mint_a,
+            &self.winner_ata_a,
+            &self.token_program_a,
+            self.vault.amount,
+            remaining_accounts,
+        )?;
+
+        if protocol_fee > 0 {
+            self.transfer_out(
+                &self.bid_vault,
+                &self.mint_b,
+                &self.fee_recipient_ata_b,
+                &self.token_program_b,
+                protocol_fee,
+                remaining_accounts,
+            )?;
+        }
+
+        self.transfer_out(
+            &self.bid_vault,
+            &self.mint_b,
+            &self.maker_ata_b,
+            &self.token_program_b,
+            self.bid_vault.amount - protocol_fee,
+            remaining_accounts,
+        )?;
+
+        // The maker funded the vault, the first bidder the bid vault
+        self.close(&self.vault, &self.mint_a, &self.token_program_a, self.maker.to_account_info())?;
+        self.close(&self.bid_vault, &self.mint_b, &self.token_program_b, self.first_bidder.to_account_info())?;
+
+        let fees = (protocol_fee > 0).then(|| FeesCollected {
+            escrow: self.escrow.key(),
+            mint: self.mint_b.key(),
+            fee_recipient: self.fee_recipient.key(),
+            referrer: None,
+            protocol_fee,
+            referral_fee: 0,
+        });
+
+        let settled = AuctionSettled {
+            escrow: self.escrow.key(),
+            seed: self.escrow.seed,
+            maker: self.maker.key(),
+            winner,
+            amount_a: self.vault.amount,
+            amount_b: auction.top_bid,
+            timestamp: Clock::get()?.unix_timestamp,
+        };
+
+        Ok((fees, settled))
+    }
+
+    fn transfer_out(
+        &self,
+        from: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        token_program: &Interface<'info, TokenInterface>,
+        amount: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let accounts = TransferChecked {
+            from: from.to_account_info(),
+            mint: mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(token_program.to_account_info(), accounts, &signer_seeds)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(ctx, amount, mint.decimals)
+    }
+
+    fn close(
+        &self,
+        account: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        token_program: &Interface<'info, TokenInterface>,
+        destination: AccountInfo<'info>,
+    ) -> Result<()> {
+        transfer_fee::harvest(token_program.to_account_info(), mint.to_account_info(), account.to_account_info())?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let accounts = CloseAccount {
+            account: account.to_account_info(),
+            destination,
+            authority: self.escrow.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(token_program.to_account_info(), accounts, &signer_seeds);
+
+        close_account(ctx)
+    }
+}
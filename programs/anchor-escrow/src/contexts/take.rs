@@ -1,82 +1,333 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{close_account, transfer_checked, Mint, TokenAccount, TokenInterface, CloseAccount, TransferChecked},
+    metadata::{Metadata, MetadataAccount},
+    token_interface::{close_account, Mint, TokenAccount, TokenInterface, CloseAccount, TransferChecked},
 };
 
-use crate::{Escrow, EscrowErrors};
+use crate::{nft, oracle::PythPrice, transfer_fee, transfer_hook::transfer_checked, Config, Escrow, EscrowErrors, EscrowTaken, FeesCollected};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Take<'info> {
     #[account(mut)]
     pub taker: Signer<'info>,
     #[account(mut)]
     pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        mint::token_program = token_program_a,
+    )]
     pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mint::token_program = token_program_b,
+    )]
     pub mint_b: InterfaceAccount<'info, Mint>,
     #[account(
         init_if_needed,
         payer = taker,
         associated_token::mint = mint_a,
         associated_token::authority = taker,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_a,
     )]
-    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub taker_ata_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
     #[account(
         mut,
         associated_token::mint = mint_b,
         associated_token::authority = taker,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_b,
     )]
-    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub taker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
     #[account(
         init_if_needed,
         payer = taker,
         associated_token::mint = mint_b,
         associated_token::authority = maker,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_b,
+    )]
+    pub maker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(mut)]
+    pub fee_recipient: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = token_program_b,
+    )]
+    pub fee_recipient_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub referrer: Option<SystemAccount<'info>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = referrer,
+        associated_token::token_program = token_program_b,
     )]
-    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub referrer_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
     #[account(
         mut,
-        close = maker,
         has_one = maker,
         has_one = mint_a,
-        has_one = mint_b,
+        constraint = escrow.accepts_mint_b(&mint_b.key()) @ ErrorCode::ConstraintHasOne,
         seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump
     )]
-    escrow: Account<'info, Escrow>,
+    pub escrow: Account<'info, Escrow>,
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_a,
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), mint_b.key().as_ref()],
+        seeds::program = Metadata::id(),
+        bump,
     )]
-    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub metadata_b: Option<Box<Account<'info, MetadataAccount>>>,
+    /// CHECK: Pyth price update, matched against the Escrow's feed id and parsed on use
+    pub price_feed: Option<UncheckedAccount<'info>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_program: Interface<'info, TokenInterface>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Take<'info> {
-    pub fn deposit(&mut self) -> Result<()> {        
-        require_gte!(self.escrow.expiry, Clock::get()?.unix_timestamp, EscrowErrors::EscrowExpired);
+    pub fn check_taker(&self, proof: &[[u8; 32]]) -> Result<()> {
+        require!(self.escrow.is_allowed(self.taker.key, proof), EscrowErrors::TakerNotAllowed);
+        Ok(())
+    }
+
+    /// Prices a Dutch auction or oracle pegged Escrow at the current time: until the
+    /// fill is done, `receive` holds the price of everything left in the vault.
+    /// Returns the floor.
+    pub fn price_receive(&mut self) -> Result<u64> {
+        let floor = self.escrow.receive;
+        let now = Clock::get()?.unix_timestamp;
+
+        if let Some(auction) = self.escrow.auction {
+            self.escrow.receive = auction.price(floor, now);
+        }
+
+        if let Some(terms) = self.escrow.oracle {
+            let price_feed = self.price_feed.as_ref().ok_or(EscrowErrors::InvalidPriceFeed)?;
+            let price = PythPrice::load(price_feed, &terms.feed_id)?;
+            price.check(now, terms.max_age, terms.max_conf_bps)?;
+
+            let quote = price.quote(self.escrow.amount, self.mint_a.decimals, self.mint_b.decimals, terms.spread_bps)?;
+            self.escrow.receive = quote.max(floor);
+        }
+
+        Ok(floor)
+    }
+
+    /// Puts the floor back after a partial fill of a priced Escrow, with a Dutch
+    /// auction schedule scaled down to what is left.
+    pub fn restore_floor(&mut self, floor: u64, amount_b: u64) -> Result<()> {
+        if (self.escrow.auction.is_none() && self.escrow.oracle.is_none()) || self.escrow.receive == 0 {
+            return Ok(());
+        }
+
+        let left = self.escrow.receive;
+        self.escrow.receive = floor;
+        self.escrow.rescale(left + amount_b, left)
+    }
+
+    pub fn check_nft(&self, amount_b: u64) -> Result<()> {
+        let terms = self.escrow.nft;
+
+        // An NFT can't be split across fills
+        if terms.nft_a {
+            require_eq!(amount_b, self.escrow.receive, EscrowErrors::InvalidFillAmount);
+        }
+
+        if terms.nft_b {
+            require!(nft::is_nft(&self.mint_b), EscrowErrors::NotAnNft);
+            require_eq!(amount_b, 1, EscrowErrors::InvalidFillAmount);
+        }
+
+        if terms.collection_b {
+            let metadata_b = self.metadata_b.as_ref().ok_or(EscrowErrors::MetadataRequired)?;
+            require!(nft::in_collection(metadata_b, &self.escrow.mint_b), EscrowErrors::CollectionNotVerified);
+        }
+
+        Ok(())
+    }
+
+    pub fn deposit(
+        &mut self,
+        amount: u64,
+        max_amount_b: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<Option<FeesCollected>> {
+        let clock = Clock::get()?;
+        require_gte!(clock.unix_timestamp, self.escrow.start_at, EscrowErrors::EscrowNotStarted);
+        require!(!self.escrow.expiry.has_passed(&clock), EscrowErrors::EscrowExpired);
+        require!(self.escrow.english.is_none(), EscrowErrors::BiddingOnly);
+        require!(amount > 0 && amount <= self.escrow.receive, EscrowErrors::InvalidFillAmount);
+
+        let (protocol_fee, referral_fee) = self.config.split_fee(amount, self.referrer.is_some())?;
+        let proceeds = amount - protocol_fee - referral_fee;
+
+        // Gross up the maker's proceeds by the mint B transfer fee when receive is net
+        let transfer_fee = match self.escrow.net_receive {
+            true => transfer_fee::inverse_fee(&self.mint_b.to_account_info(), proceeds)?,
+            false => 0,
+        };
+
+        let total = amount.checked_add(transfer_fee).ok_or(EscrowErrors::MathOverflow)?;
+        require_gte!(max_amount_b, total, EscrowErrors::SlippageExceeded);
+
+        self.pay(
+            self.maker.to_account_info(),
+            self.maker_ata_b.as_deref(),
+            proceeds + transfer_fee,
+            remaining_accounts,
+        )?;
+
+        if protocol_fee > 0 {
+            self.pay(
+                self.fee_recipient.to_account_info(),
+                self.fee_recipient_ata_b.as_deref(),
+                protocol_fee,
+                remaining_accounts,
+            )?;
+        }
+
+        if let Some(referrer) = &self.referrer {
+            if referral_fee > 0 {
+                self.pay(
+                    referrer.to_account_info(),
+                    self.referrer_ata_b.as_deref(),
+                    referral_fee,
+                    remaining_accounts,
+                )?;
+            }
+        }
+
+        Ok((protocol_fee + referral_fee > 0).then(|| FeesCollected {
+            escrow: self.escrow.key(),
+            mint: self.mint_b.key(),
+            fee_recipient: self.fee_recipient.key(),
+            referrer: self.referrer.as_ref().map(|referrer| referrer.key()),
+            protocol_fee,
+            referral_fee,
+        }))
+    }
+
+    /// Pays mint B to a wallet, in lamports when mint B is native SOL and to its
+    /// token account otherwise.
+    fn pay(
+        &self,
+        to: AccountInfo<'info>,
+        to_ata: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        if self.escrow.is_native_b() {
+            let transfer_accounts = Transfer {
+                from: self.taker.to_account_info(),
+                to,
+            };
+
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+
+            return transfer(cpi_ctx, amount);
+        }
+
+        let taker_ata_b = self.taker_ata_b.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+        let to_ata = to_ata.ok_or(EscrowErrors::TokenAccountRequired)?;
 
         let transfer_accounts = TransferChecked {
-            from: self.taker_ata_b.to_account_info(),
+            from: taker_ata_b.to_account_info(),
             mint: self.mint_b.to_account_info(),
-            to: self.maker_ata_b.to_account_info(),
+            to: to_ata.to_account_info(),
             authority: self.taker.to_account_info(),
         };
 
-        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(cpi_ctx, amount, self.mint_b.decimals)
+    }
+
+    pub fn withdraw(
+        &mut self,
+        amount_b: u64,
+        min_amount_a: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<EscrowTaken> {
+        let available = self.escrow.amount;
+
+        // Release mint A pro-rata to the share of the remaining receive being paid,
+        // rounding down so the vault always keeps enough for the rest of the fills
+        let amount_a = match amount_b == self.escrow.receive {
+            true => available,
+            false => u64::try_from(
+                (available as u128)
+                    .checked_mul(amount_b as u128)
+                    .ok_or(EscrowErrors::MathOverflow)?
+                    / self.escrow.receive as u128,
+            )
+            .map_err(|_| EscrowErrors::MathOverflow)?,
+        };
+        require!(amount_a > 0, EscrowErrors::InvalidFillAmount);
+
+        let received = amount_a - transfer_fee::fee(&self.mint_a.to_account_info(), amount_a)?;
+        require_gte!(received, min_amount_a, EscrowErrors::SlippageExceeded);
+
+        self.release(amount_a, remaining_accounts)?;
+
+        self.escrow.amount -= amount_a;
+        self.escrow.receive -= amount_b;
+
+        let event = EscrowTaken {
+            escrow: self.escrow.key(),
+            seed: self.escrow.seed,
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            amount_a,
+            amount_b,
+            remaining_receive: self.escrow.receive,
+            expiry: self.escrow.expiry,
+            timestamp: Clock::get()?.unix_timestamp,
+        };
+
+        if self.escrow.receive == 0 {
+            self.close_vault(remaining_accounts)?;
+        }
 
-        transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
+        Ok(event)
     }
 
-    pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
+    /// Sends mint A to the taker, moving lamports out of the Escrow account when
+    /// mint A is native SOL.
+    fn release(&self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        if self.escrow.is_native_a() {
+            self.escrow.sub_lamports(amount)?;
+            self.taker.add_lamports(amount)?;
+            return Ok(());
+        }
+
+        let taker_ata_a = self.taker_ata_a.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+        let vault = self.vault.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.to_account_info().key.as_ref(),
@@ -85,32 +336,62 @@ impl<'info> Take<'info> {
         ]];
 
         let accounts = TransferChecked {
-            from: self.vault.to_account_info(),
+            from: vault.to_account_info(),
             mint: self.mint_a.to_account_info(),
-            to: self.taker_ata_a.to_account_info(),
+            to: taker_ata_a.to_account_info(),
             authority: self.escrow.to_account_info(),
         };
 
         let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
+            self.token_program_a.to_account_info(),
             accounts,
             &signer_seeds,
-        );
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
 
-        transfer_checked(ctx, self.vault.amount, self.mint_a.decimals)?;
+        transfer_checked(ctx, amount, self.mint_a.decimals)
+    }
 
-        let accounts = CloseAccount {
-            account: self.vault.to_account_info(),
-            destination: self.taker.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
+    /// Closes the emptied vault and the Escrow account once the last unit is filled.
+    fn close_vault(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        if let Some(vault) = self.vault.as_mut() {
+            vault.reload()?;
+        }
 
-        let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            accounts,
-            &signer_seeds,
-        );
+        if let Some(vault) = &self.vault {
+            // Sweep whatever was sent to the vault on top of the offered amount
+            if vault.amount > 0 {
+                self.release(vault.amount, remaining_accounts)?;
+            }
+
+            transfer_fee::harvest(
+                self.token_program_a.to_account_info(),
+                self.mint_a.to_account_info(),
+                vault.to_account_info(),
+            )?;
+
+            let signer_seeds: [&[&[u8]]; 1] = [&[
+                b"escrow",
+                self.maker.to_account_info().key.as_ref(),
+                &self.escrow.seed.to_le_bytes()[..],
+                &[self.escrow.bump],
+            ]];
+
+            let accounts = CloseAccount {
+                account: vault.to_account_info(),
+                destination: self.taker.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+
+            let ctx = CpiContext::new_with_signer(
+                self.token_program_a.to_account_info(),
+                accounts,
+                &signer_seeds,
+            );
+
+            close_account(ctx)?;
+        }
 
-        close_account(ctx)
+        self.escrow.close(self.maker.to_account_info())
     }
 }
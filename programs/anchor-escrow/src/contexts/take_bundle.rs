@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::{
+    bundle::{self, LegAccounts},
+    Bundle, BundleTaken, Config, EscrowErrors, FeesCollected,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TakeBundle<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"bundle", maker.key().as_ref(), bundle.seed.to_le_bytes().as_ref()],
+        bump = bundle.bump
+    )]
+    pub bundle: Account<'info, Bundle>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+    )]
+    pub config: Account<'info, Config>,
+    pub fee_recipient: SystemAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeBundle<'info> {
+    /// Pays every `receive` leg to the maker, less the protocol fee, and releases
+    /// every vault to the taker. The remaining accounts hold the `give` legs first,
+    /// then the `receive` legs, then the fee recipient's token account of every
+    /// `receive` leg. Returns the fees collected on each `receive` leg.
+    pub fn take_bundle(&self, remaining_accounts: &[AccountInfo<'info>]) -> Result<(Vec<FeesCollected>, BundleTaken)> {
+        let clock = Clock::get()?;
+        require_gte!(clock.unix_timestamp, self.bundle.start_at, EscrowErrors::EscrowNotStarted);
+        require!(!self.bundle.expiry.has_passed(&clock), EscrowErrors::EscrowExpired);
+
+        let (give_accounts, remaining_accounts) = remaining_accounts
+            .split_at((self.bundle.give.len() * bundle::LEG_ACCOUNTS).min(remaining_accounts.len()));
+        let (receive_accounts, fee_accounts) = remaining_accounts
+            .split_at((self.bundle.receive.len() * bundle::LEG_ACCOUNTS).min(remaining_accounts.len()));
+        require_eq!(fee_accounts.len(), self.bundle.receive.len(), EscrowErrors::BundleAccountMismatch);
+
+        let taker = self.taker.to_account_info();
+        let associated_token_program = self.associated_token_program.to_account_info();
+        let system_program = self.system_program.to_account_info();
+        let mut fees = Vec::new();
+
+        for (leg, fee_account) in bundle::split(receive_accounts, &self.bundle.receive)?.into_iter().zip(fee_accounts) {
+            let (protocol_fee, _) = self.config.split_fee(leg.leg.amount, false)?;
+
+            leg.init_destination(&self.maker.to_account_info(), &taker, &associated_token_program, &system_program)?;
+            leg.transfer(&taker, leg.leg.amount - protocol_fee, &[])?;
+
+            if protocol_fee > 0 {
+                let fee_leg = LegAccounts { destination: fee_account, ..leg };
+                fee_leg.init_destination(&self.fee_recipient.to_account_info(), &taker, &associated_token_program, &system_program)?;
+                fee_leg.transfer(&taker, protocol_fee, &[])?;
+
+                fees.push(FeesCollected {
+                    escrow: self.bundle.key(),
+                    mint: leg.leg.mint,
+                    fee_recipient: self.fee_recipient.key(),
+                    referrer: None,
+                    protocol_fee,
+                    referral_fee: 0,
+                });
+            }
+        }
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bundle",
+            self.maker.to_account_info().key.as_ref(),
+            &self.bundle.seed.to_le_bytes()[..],
+            &[self.bundle.bump],
+        ]];
+
+        for leg in bundle::split(give_accounts, &self.bundle.give)? {
+            leg.check_source(&self.bundle.key())?;
+            leg.init_destination(&taker, &taker, &associated_token_program, &system_program)?;
+            leg.sweep_source(&self.bundle.to_account_info(), &taker, &signer_seeds)?;
+        }
+
+        let taken = BundleTaken {
+            bundle: self.bundle.key(),
+            seed: self.bundle.seed,
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            timestamp: clock.unix_timestamp,
+        };
+
+        Ok((fees, taken))
+    }
+}
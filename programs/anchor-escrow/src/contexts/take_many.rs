@@ -0,0 +1,288 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create},
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    nft, transfer_fee, transfer_hook::transfer_checked, Config, Escrow, EscrowErrors, EscrowTaken, FeesCollected,
+};
+
+// Every Escrow filled by `take_many` is passed through the remaining accounts as
+// `[escrow, vault, maker, maker token account of mint B]`. Since they hold the
+// Escrows, mints with a transfer hook can't be filled this way. Neither can Escrows
+// with an allowlist, whose Merkle proofs the instruction has no room for.
+
+pub const ESCROW_ACCOUNTS: usize = 4;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TakeMany<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(
+        mut,
+        mint::token_program = token_program_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_b,
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a,
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b,
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_recipient,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    pub fee_recipient: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = token_program_b,
+    )]
+    pub fee_recipient_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeMany<'info> {
+    /// Fills whole every Escrow of the remaining accounts, all trading mint A for
+    /// mint B. Slippage bounds apply to the totals, the protocol fees of every fill
+    /// are paid in a single transfer, and any failing fill fails them all. Returns
+    /// the events of every fill.
+    pub fn take_many(
+        &self,
+        min_amount_a: u64,
+        max_amount_b: u64,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<Vec<(Option<FeesCollected>, EscrowTaken)>> {
+        let chunks = remaining_accounts.chunks_exact(ESCROW_ACCOUNTS);
+        require!(
+            !remaining_accounts.is_empty() && chunks.remainder().is_empty(),
+            EscrowErrors::EscrowAccountMismatch
+        );
+
+        let clock = Clock::get()?;
+        let mut received_a: u64 = 0;
+        let mut total_b: u64 = 0;
+        let mut total_fee: u64 = 0;
+        let mut events = Vec::with_capacity(remaining_accounts.len() / ESCROW_ACCOUNTS);
+
+        for accounts in chunks {
+            let [escrow, vault, maker, maker_ata_b] = accounts else {
+                unreachable!()
+            };
+
+            let escrow = Account::<Escrow>::try_from(escrow)?;
+            self.check_escrow(&escrow, vault, maker, &clock)?;
+
+            let amount_b = match escrow.auction {
+                Some(auction) => auction.price(escrow.receive, clock.unix_timestamp),
+                None => escrow.receive,
+            };
+
+            let (protocol_fee, _) = self.config.split_fee(amount_b, false)?;
+            let proceeds = amount_b - protocol_fee;
+
+            // Gross up the maker's proceeds by the mint B transfer fee when receive is net
+            let transfer_fee = match escrow.net_receive {
+                true => transfer_fee::inverse_fee(&self.mint_b.to_account_info(), proceeds)?,
+                false => 0,
+            };
+
+            self.pay_maker(maker, maker_ata_b, proceeds + transfer_fee)?;
+            let amount_a = self.sweep_vault(&escrow, vault, maker)?;
+
+            received_a = received_a
+                .checked_add(amount_a - transfer_fee::fee(&self.mint_a.to_account_info(), amount_a)?)
+                .ok_or(EscrowErrors::MathOverflow)?;
+            total_b = total_b
+                .checked_add(amount_b + transfer_fee)
+                .ok_or(EscrowErrors::MathOverflow)?;
+            total_fee += protocol_fee;
+
+            let fees = (protocol_fee > 0).then(|| FeesCollected {
+                escrow: escrow.key(),
+                mint: self.mint_b.key(),
+                fee_recipient: self.fee_recipient.key(),
+                referrer: None,
+                protocol_fee,
+                referral_fee: 0,
+            });
+
+            let taken = EscrowTaken {
+                escrow: escrow.key(),
+                seed: escrow.seed,
+                maker: maker.key(),
+                taker: self.taker.key(),
+                mint_a: self.mint_a.key(),
+                mint_b: self.mint_b.key(),
+                amount_a,
+                amount_b,
+                remaining_receive: 0,
+                expiry: escrow.expiry,
+                timestamp: clock.unix_timestamp,
+            };
+
+            events.push((fees, taken));
+            escrow.close(maker.clone())?;
+        }
+
+        require_gte!(max_amount_b, total_b, EscrowErrors::SlippageExceeded);
+        require_gte!(received_a, min_amount_a, EscrowErrors::SlippageExceeded);
+
+        if total_fee > 0 {
+            self.pay(self.fee_recipient_ata_b.to_account_info(), total_fee)?;
+        }
+
+        Ok(events)
+    }
+
+    /// Runs the checks `Take` does through its account constraints and handler, and
+    /// rejects the Escrows needing accounts, proofs or settlement that `take_many`
+    /// lacks.
+    fn check_escrow(
+        &self,
+        escrow: &Account<'info, Escrow>,
+        vault: &AccountInfo<'info>,
+        maker: &AccountInfo<'info>,
+        clock: &Clock,
+    ) -> Result<()> {
+        let escrow_key = Pubkey::create_program_address(
+            &[b"escrow", maker.key.as_ref(), &escrow.seed.to_le_bytes(), &[escrow.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| EscrowErrors::EscrowAccountMismatch)?;
+
+        require_keys_eq!(escrow.key(), escrow_key, EscrowErrors::EscrowAccountMismatch);
+        require_keys_eq!(escrow.maker, maker.key(), EscrowErrors::EscrowAccountMismatch);
+        require_keys_eq!(escrow.mint_a, self.mint_a.key(), EscrowErrors::EscrowAccountMismatch);
+        require_keys_eq!(escrow.mint_b, self.mint_b.key(), EscrowErrors::EscrowAccountMismatch);
+        require_keys_eq!(
+            vault.key(),
+            get_associated_token_address_with_program_id(&escrow_key, &escrow.mint_a, self.token_program_a.key),
+            EscrowErrors::EscrowAccountMismatch
+        );
+
+        require!(
+            !escrow.is_native_a()
+                && !escrow.is_native_b()
+                && !escrow.nft.collection_b
+                && escrow.english.is_none()
+                && escrow.oracle.is_none()
+                && escrow.allowlist.is_none(),
+            EscrowErrors::UnsupportedEscrow
+        );
+
+        if let Some(taker) = escrow.taker {
+            require_keys_eq!(taker, self.taker.key(), EscrowErrors::TakerNotAllowed);
+        }
+        require_gte!(clock.unix_timestamp, escrow.start_at, EscrowErrors::EscrowNotStarted);
+        require!(!escrow.expiry.has_passed(clock), EscrowErrors::EscrowExpired);
+
+        if escrow.nft.nft_b {
+            require!(nft::is_nft(&self.mint_b), EscrowErrors::NotAnNft);
+        }
+
+        Ok(())
+    }
+
+    /// Pays the maker's proceeds, creating its token account of mint B if needed.
+    fn pay_maker(&self, maker: &AccountInfo<'info>, maker_ata_b: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        require_keys_eq!(
+            maker_ata_b.key(),
+            get_associated_token_address_with_program_id(maker.key, &self.mint_b.key(), self.token_program_b.key),
+            EscrowErrors::EscrowAccountMismatch
+        );
+
+        if maker_ata_b.data_is_empty() {
+            let accounts = Create {
+                payer: self.taker.to_account_info(),
+                associated_token: maker_ata_b.clone(),
+                authority: maker.clone(),
+                mint: self.mint_b.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program_b.to_account_info(),
+            };
+
+            create_idempotent(CpiContext::new(self.associated_token_program.to_account_info(), accounts))?;
+        }
+
+        self.pay(maker_ata_b.clone(), amount)
+    }
+
+    fn pay(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to,
+            authority: self.taker.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(self.token_program_b.to_account_info(), accounts);
+
+        transfer_checked(ctx, amount, self.mint_b.decimals)
+    }
+
+    /// Releases everything in the vault to the taker and closes it, its rent going to
+    /// the taker as with `take`. Returns the amount released.
+    fn sweep_vault(
+        &self,
+        escrow: &Account<'info, Escrow>,
+        vault: &'info AccountInfo<'info>,
+        maker: &AccountInfo<'info>,
+    ) -> Result<u64> {
+        let amount = InterfaceAccount::<TokenAccount>::try_from(vault)?.amount;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            maker.key.as_ref(),
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump],
+        ]];
+
+        let accounts = TransferChecked {
+            from: vault.clone(),
+            mint: self.mint_a.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: escrow.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(self.token_program_a.to_account_info(), accounts, &signer_seeds);
+
+        transfer_checked(ctx, amount, self.mint_a.decimals)?;
+
+        transfer_fee::harvest(self.token_program_a.to_account_info(), self.mint_a.to_account_info(), vault.clone())?;
+
+        let accounts = CloseAccount {
+            account: vault.clone(),
+            destination: self.taker.to_account_info(),
+            authority: escrow.to_account_info(),
+        };
+
+        close_account(CpiContext::new_with_signer(self.token_program_a.to_account_info(), accounts, &signer_seeds))?;
+
+        Ok(amount)
+    }
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowErrors, EscrowUpdated, Expiry, ExpiryMode};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Update<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> Update<'info> {
+    pub fn update(&mut self, receive: Option<u64>, expiry: Option<ExpiryMode>, expected_receive: Option<u64>) -> Result<EscrowUpdated> {
+        require!(!self.escrow.has_bids(), EscrowErrors::AuctionHasBids);
+
+        // Guard against a fill landing between the maker reading the Escrow and repricing it
+        if let Some(expected_receive) = expected_receive {
+            require_eq!(self.escrow.receive, expected_receive, EscrowErrors::ReceiveMismatch);
+        }
+
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        if let Some(receive) = receive {
+            require!(receive > 0, EscrowErrors::InvalidAmount);
+            // An NFT ask stays priced at one NFT, as on make
+            let nft = self.escrow.nft;
+            require!(!nft.nft_b || nft.collection_b || receive == 1, EscrowErrors::InvalidNftTerms);
+            if let Some(auction) = self.escrow.auction {
+                auction.check(receive)?;
+            }
+            self.escrow.receive = receive;
+        }
+
+        if let Some(expiry) = expiry {
+            let expiry = expiry.resolve(timestamp)?;
+            // An English auction must keep an end time to be settled
+            require!(
+                self.escrow.english.is_none() || matches!(expiry, Expiry::Timestamp(_)),
+                EscrowErrors::InvalidAuction
+            );
+            // A staged Escrow must still open before it expires, as on make
+            if let Expiry::Timestamp(expiry) = expiry {
+                require_gt!(expiry, self.escrow.start_at, EscrowErrors::InvalidStartTime);
+            }
+            self.escrow.expiry = expiry;
+        }
+
+        Ok(EscrowUpdated {
+            escrow: self.escrow.key(),
+            seed: self.escrow.seed,
+            maker: self.maker.key(),
+            receive: self.escrow.receive,
+            expiry: self.escrow.expiry,
+            timestamp,
+        })
+    }
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowErrors};
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    pub fee_recipient: SystemAccount<'info>,
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    pub fn update_config(&mut self, fee_bps: u16, referral_bps: u16) -> Result<()> {
+        require!(fee_bps <= Config::MAX_FEE_BPS && referral_bps <= Config::MAX_BPS, EscrowErrors::InvalidFee);
+
+        self.config.fee_recipient = self.fee_recipient.key();
+        self.config.fee_bps = fee_bps;
+        self.config.referral_bps = referral_bps;
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{transfer_hook::transfer_checked, Escrow, EscrowErrors, EscrowResized};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawSome<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawSome<'info> {
+    pub fn withdraw_some(
+        &mut self,
+        amount: u64,
+        scale_receive: bool,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<EscrowResized> {
+        require!(!self.escrow.has_bids(), EscrowErrors::AuctionHasBids);
+
+        let available = self.escrow.amount;
+
+        // Withdrawing everything is what refund is for
+        require!(amount > 0 && amount < available, EscrowErrors::InvalidAmount);
+
+        // Native SOL is moved straight out of the Escrow account
+        if self.escrow.is_native_a() {
+            self.escrow.sub_lamports(amount)?;
+            self.maker.add_lamports(amount)?;
+        } else {
+            let maker_ata_a = self.maker_ata_a.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+            let vault = self.vault.as_ref().ok_or(EscrowErrors::TokenAccountRequired)?;
+
+            let signer_seeds: [&[&[u8]]; 1] = [&[
+                b"escrow",
+                self.maker.to_account_info().key.as_ref(),
+                &self.escrow.seed.to_le_bytes()[..],
+                &[self.escrow.bump],
+            ]];
+
+            let transfer_accounts = TransferChecked {
+                from: vault.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: maker_ata_a.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program_a.to_account_info(),
+                transfer_accounts,
+                &signer_seeds,
+            )
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+            transfer_checked(cpi_ctx, amount, self.mint_a.decimals)?;
+        }
+
+        let amount_a = available - amount;
+
+        self.escrow.amount = amount_a;
+
+        if scale_receive {
+            self.escrow.rescale(available, amount_a)?;
+        }
+
+        Ok(EscrowResized {
+            escrow: self.escrow.key(),
+            seed: self.escrow.seed,
+            maker: self.maker.key(),
+            amount_a,
+            receive: self.escrow.receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
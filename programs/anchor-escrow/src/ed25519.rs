@@ -0,0 +1,65 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        ed25519_program,
+        sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+};
+
+use crate::EscrowErrors;
+
+// The Ed25519 native program verifies the signatures listed in its instruction data
+// and fails the whole transaction on a bad one. A program confirms what was verified
+// by reading that instruction back from the instructions sysvar.
+
+const HEADER_LEN: usize = 16;
+const PUBKEY_OFFSET: usize = HEADER_LEN;
+const SIGNATURE_OFFSET: usize = PUBKEY_OFFSET + 32;
+const MESSAGE_OFFSET: usize = SIGNATURE_OFFSET + 64;
+
+/// Offsets pointing into the instruction's own data, for a single signature.
+fn header(message_len: usize) -> [u8; HEADER_LEN] {
+    let this_instruction = u16::MAX;
+    let fields = [
+        SIGNATURE_OFFSET as u16,
+        this_instruction,
+        PUBKEY_OFFSET as u16,
+        this_instruction,
+        MESSAGE_OFFSET as u16,
+        message_len as u16,
+        this_instruction,
+    ];
+
+    let mut header = [0u8; HEADER_LEN];
+    header[0] = 1;
+    for (i, field) in fields.iter().enumerate() {
+        header[2 + 2 * i..4 + 2 * i].copy_from_slice(&field.to_le_bytes());
+    }
+    header
+}
+
+/// Data of an Ed25519 program instruction verifying `signature` of `message` by `signer`.
+pub fn instruction_data(signer: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Vec<u8> {
+    [&header(message.len())[..], signer.as_ref(), signature, message].concat()
+}
+
+/// Checks that the instruction right before the current one has the Ed25519 program
+/// verify a signature of exactly `message` by `signer`.
+pub fn verify(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current = load_current_index_checked(instructions)?;
+    require_gt!(current, 0, EscrowErrors::InvalidOrderSignature);
+
+    let instruction = load_instruction_at_checked(current as usize - 1, instructions)?;
+    let data = &instruction.data;
+
+    require!(
+        instruction.program_id == ed25519_program::ID
+            && data.len() == MESSAGE_OFFSET + message.len()
+            && data[..HEADER_LEN] == header(message.len())
+            && data[PUBKEY_OFFSET..SIGNATURE_OFFSET] == signer.to_bytes()
+            && data[MESSAGE_OFFSET..] == *message,
+        EscrowErrors::InvalidOrderSignature
+    );
+
+    Ok(())
+}
@@ -4,4 +4,86 @@ use anchor_lang::error_code;
 pub enum EscrowErrors {
     #[msg("Escrow Expired")]
     EscrowExpired,
+    #[msg("Invalid Fill Amount")]
+    InvalidFillAmount,
+    #[msg("Math Overflow")]
+    MathOverflow,
+    #[msg("Taker Not Allowed")]
+    TakerNotAllowed,
+    #[msg("Invalid Fee")]
+    InvalidFee,
+    #[msg("Token Account Required")]
+    TokenAccountRequired,
+    #[msg("Receive Mismatch")]
+    ReceiveMismatch,
+    #[msg("Invalid Amount")]
+    InvalidAmount,
+    #[msg("Slippage Exceeded")]
+    SlippageExceeded,
+    #[msg("Escrow Not Expired")]
+    EscrowNotExpired,
+    #[msg("Permanent Delegate Not Allowed")]
+    PermanentDelegateNotAllowed,
+    #[msg("Default Frozen Not Allowed")]
+    DefaultFrozenNotAllowed,
+    #[msg("Non Transferable Not Allowed")]
+    NonTransferableNotAllowed,
+    #[msg("Freeze Authority Not Allowed")]
+    FreezeAuthorityNotAllowed,
+    #[msg("Close Authority Not Allowed")]
+    CloseAuthorityNotAllowed,
+    #[msg("Not An Nft")]
+    NotAnNft,
+    #[msg("Invalid Nft Terms")]
+    InvalidNftTerms,
+    #[msg("Metadata Account Required")]
+    MetadataRequired,
+    #[msg("Collection Not Verified")]
+    CollectionNotVerified,
+    #[msg("Invalid Bundle")]
+    InvalidBundle,
+    #[msg("Bundle Account Mismatch")]
+    BundleAccountMismatch,
+    #[msg("Invalid Auction")]
+    InvalidAuction,
+    #[msg("Not An Auction")]
+    NotAnAuction,
+    #[msg("Escrow Only Accepts Bids")]
+    BiddingOnly,
+    #[msg("Bid Too Low")]
+    BidTooLow,
+    #[msg("Bidder Mismatch")]
+    BidderMismatch,
+    #[msg("Auction Has Bids")]
+    AuctionHasBids,
+    #[msg("No Bids")]
+    NoBids,
+    #[msg("Invalid Oracle Terms")]
+    InvalidOracleTerms,
+    #[msg("Invalid Price Feed")]
+    InvalidPriceFeed,
+    #[msg("Stale Price")]
+    StalePrice,
+    #[msg("Price Uncertain")]
+    PriceUncertain,
+    #[msg("Escrow Not Started")]
+    EscrowNotStarted,
+    #[msg("Invalid Start Time")]
+    InvalidStartTime,
+    #[msg("Invalid Order Signature")]
+    InvalidOrderSignature,
+    #[msg("Order Nonce Used")]
+    OrderNonceUsed,
+    #[msg("Order Cancelled")]
+    OrderCancelled,
+    #[msg("Invalid Nonce")]
+    InvalidNonce,
+    #[msg("Escrow Account Mismatch")]
+    EscrowAccountMismatch,
+    #[msg("Unsupported Escrow")]
+    UnsupportedEscrow,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Unsupported Bundle Mint")]
+    UnsupportedBundleMint,
 }
\ No newline at end of file
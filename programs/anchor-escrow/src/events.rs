@@ -0,0 +1,145 @@
+use anchor_lang::prelude::*;
+
+use crate::{BundleLeg, Expiry};
+
+#[event]
+pub struct EscrowMade {
+    pub escrow: Pubkey,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub mint_a_flags: u8,
+    pub deposit: u64,
+    pub receive: u64,
+    pub start_at: i64,
+    pub expiry: Expiry,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowTaken {
+    pub escrow: Pubkey,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub remaining_receive: u64,
+    pub expiry: Expiry,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowUpdated {
+    pub escrow: Pubkey,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub receive: u64,
+    pub expiry: Expiry,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowResized {
+    pub escrow: Pubkey,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub amount_a: u64,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowRefunded {
+    pub escrow: Pubkey,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64,
+    pub expiry: Expiry,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesCollected {
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub referrer: Option<Pubkey>,
+    pub protocol_fee: u64,
+    pub referral_fee: u64,
+}
+
+#[event]
+pub struct BundleMade {
+    pub bundle: Pubkey,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub give: Vec<BundleLeg>,
+    pub receive: Vec<BundleLeg>,
+    pub start_at: i64,
+    pub expiry: Expiry,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleTaken {
+    pub bundle: Pubkey,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleRefunded {
+    pub bundle: Pubkey,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub escrow: Pubkey,
+    pub bidder: Pubkey,
+    pub previous_bidder: Option<Pubkey>,
+    pub amount: u64,
+    pub expiry: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionSettled {
+    pub escrow: Pubkey,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub winner: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SignedOrderFilled {
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub protocol_fee: u64,
+    pub nonce: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrdersCancelled {
+    pub maker: Pubkey,
+    pub min_nonce: u64,
+    pub timestamp: i64,
+}
@@ -1,31 +1,229 @@
 use anchor_lang::prelude::*;
 
+pub mod bundle;
+
 pub mod contexts;
 use contexts::*;
 
+pub mod ed25519;
+
 pub mod errors;
 pub use errors::*;
 
+pub mod events;
+pub use events::*;
+
+pub mod merkle;
+
+pub mod mint_policy;
+
+pub mod nft;
+
+pub mod oracle;
+
 pub mod state;
 pub use state::*;
 
+pub mod transfer_fee;
+
+pub mod transfer_hook;
+
 declare_id!("6BLPdL9narQPFQsqS7AXuRBRS4VoyKmHHzdwkgnLaAps");
 
 #[program]
 pub mod anchor_escrow {
     use super::*;
 
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64, expiry: u64) -> Result<()> {
-        ctx.accounts.deposit(deposit)?;
-        ctx.accounts.save_escrow(seed, receive, expiry, &ctx.bumps)
+    #[allow(clippy::too_many_arguments)]
+    pub fn make<'info>(
+        ctx: Context<'_, '_, '_, 'info, Make<'info>>,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        net_receive: bool,
+        expiry: ExpiryMode,
+        taker: Option<Pubkey>,
+        allowlist: Option<[u8; 32]>,
+        allowed_mint_flags: u8,
+        nft: NftTerms,
+        auction: Option<DutchAuction>,
+        english: Option<EnglishTerms>,
+        oracle: Option<OracleTerms>,
+        start_at: Option<TimeSpec>,
+    ) -> Result<()> {
+        ctx.accounts.check_mint_a(allowed_mint_flags)?;
+        ctx.accounts.check_nft(&nft, deposit, receive)?;
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts)?;
+        let event = ctx.accounts.save_escrow(seed, deposit, receive, net_receive, expiry, taker, allowlist, nft, auction, english, oracle, start_at, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn init_config(ctx: Context<InitConfig>, fee_bps: u16, referral_bps: u16) -> Result<()> {
+        ctx.accounts.init_config(fee_bps, referral_bps, &ctx.bumps)
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, fee_bps: u16, referral_bps: u16) -> Result<()> {
+        ctx.accounts.update_config(fee_bps, referral_bps)
+    }
+
+    pub fn update(
+        ctx: Context<Update>,
+        receive: Option<u64>,
+        expiry: Option<ExpiryMode>,
+        expected_receive: Option<u64>,
+    ) -> Result<()> {
+        let event = ctx.accounts.update(receive, expiry, expected_receive)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn deposit_more<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositMore<'info>>,
+        amount: u64,
+        scale_receive: bool,
+    ) -> Result<()> {
+        let event = ctx.accounts.deposit_more(amount, scale_receive, ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn withdraw_some<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawSome<'info>>,
+        amount: u64,
+        scale_receive: bool,
+    ) -> Result<()> {
+        let event = ctx.accounts.withdraw_some(amount, scale_receive, ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn refund<'info>(ctx: Context<'_, '_, '_, 'info, Refund<'info>>) -> Result<()> {
+        let event = ctx.accounts.refund_and_close_vault(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn close_expired<'info>(ctx: Context<'_, '_, '_, 'info, CloseExpired<'info>>) -> Result<()> {
+        let event = ctx.accounts.close_expired(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn take<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        min_amount_a: u64,
+        max_amount_b: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.check_taker(&proof)?;
+        ctx.accounts.price_receive()?;
+        let receive = ctx.accounts.escrow.receive;
+        ctx.accounts.check_nft(receive)?;
+        let fees = ctx.accounts.deposit(receive, max_amount_b, ctx.remaining_accounts)?;
+        let taken = ctx.accounts.withdraw(receive, min_amount_a, ctx.remaining_accounts)?;
+        if let Some(fees) = fees {
+            emit_cpi!(fees);
+        }
+        emit_cpi!(taken);
+        Ok(())
+    }
+
+    pub fn take_partial<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        amount_b: u64,
+        min_amount_a: u64,
+        max_amount_b: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.check_taker(&proof)?;
+        let floor = ctx.accounts.price_receive()?;
+        ctx.accounts.check_nft(amount_b)?;
+        let fees = ctx.accounts.deposit(amount_b, max_amount_b, ctx.remaining_accounts)?;
+        let taken = ctx.accounts.withdraw(amount_b, min_amount_a, ctx.remaining_accounts)?;
+        ctx.accounts.restore_floor(floor, amount_b)?;
+        if let Some(fees) = fees {
+            emit_cpi!(fees);
+        }
+        emit_cpi!(taken);
+        Ok(())
+    }
+
+    pub fn take_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeMany<'info>>,
+        min_amount_a: u64,
+        max_amount_b: u64,
+    ) -> Result<()> {
+        let events = ctx.accounts.take_many(min_amount_a, max_amount_b, ctx.remaining_accounts)?;
+        for (fees, taken) in events {
+            if let Some(fees) = fees {
+                emit_cpi!(fees);
+            }
+            emit_cpi!(taken);
+        }
+        Ok(())
+    }
+
+    pub fn bid<'info>(
+        ctx: Context<'_, '_, '_, 'info, Bid<'info>>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let event = ctx.accounts.bid(amount, &proof, ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn settle_auction<'info>(ctx: Context<'_, '_, '_, 'info, SettleAuction<'info>>) -> Result<()> {
+        let (fees, settled) = ctx.accounts.settle_auction(ctx.remaining_accounts)?;
+        if let Some(fees) = fees {
+            emit_cpi!(fees);
+        }
+        emit_cpi!(settled);
+        Ok(())
+    }
+
+    pub fn fill_signed_order<'info>(
+        ctx: Context<'_, '_, '_, 'info, FillSignedOrder<'info>>,
+        order: SignedOrder,
+    ) -> Result<()> {
+        let event = ctx.accounts.fill_signed_order(&order, &ctx.bumps, ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn cancel_orders_up_to(ctx: Context<CancelOrders>, nonce: u64) -> Result<()> {
+        let event = ctx.accounts.cancel_orders_up_to(nonce, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn make_bundle<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeBundle<'info>>,
+        seed: u64,
+        give: Vec<BundleLeg>,
+        receive: Vec<BundleLeg>,
+        expiry: ExpiryMode,
+        start_at: Option<TimeSpec>,
+    ) -> Result<()> {
+        let event = ctx.accounts.save_bundle(seed, give, receive, expiry, start_at, &ctx.bumps)?;
+        ctx.accounts.deposit(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
     }
 
-    pub fn refund(ctx: Context<Refund>) -> Result<()> {
-        ctx.accounts.refund_and_close_vault()
+    pub fn take_bundle<'info>(ctx: Context<'_, '_, '_, 'info, TakeBundle<'info>>) -> Result<()> {
+        let (fees, taken) = ctx.accounts.take_bundle(ctx.remaining_accounts)?;
+        for fees in fees {
+            emit_cpi!(fees);
+        }
+        emit_cpi!(taken);
+        Ok(())
     }
 
-    pub fn take(ctx: Context<Take>) -> Result<()> {
-        ctx.accounts.deposit()?;
-        ctx.accounts.withdraw_and_close_vault()
+    pub fn refund_bundle<'info>(ctx: Context<'_, '_, '_, 'info, RefundBundle<'info>>) -> Result<()> {
+        let event = ctx.accounts.refund_bundle(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
     }
 }
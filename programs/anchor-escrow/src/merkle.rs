@@ -0,0 +1,20 @@
+use anchor_lang::solana_program::hash::hashv;
+
+/// Leaf of the allowlist Merkle tree for a given taker.
+pub fn leaf(key: &[u8]) -> [u8; 32] {
+    hashv(&[&[0u8], key]).to_bytes()
+}
+
+/// Walks the proof from the leaf up, hashing sorted pairs, and checks the result
+/// against the root.
+pub fn verify(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&[1u8], &node, sibling]).to_bytes()
+        } else {
+            hashv(&[&[1u8], sibling, &node]).to_bytes()
+        }
+    });
+
+    computed == root
+}
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_2022::{
+    spl_token_2022::{
+        extension::{
+            default_account_state::DefaultAccountState, mint_close_authority::MintCloseAuthority,
+            non_transferable::NonTransferable, permanent_delegate::PermanentDelegate,
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::{AccountState, Mint},
+    },
+};
+
+use crate::EscrowErrors;
+
+// Mint features that let a third party freeze, move or strand the tokens held in a
+// vault. A maker passes the set of features they accept on mint A to `make`.
+
+pub const PERMANENT_DELEGATE: u8 = 1 << 0;
+pub const DEFAULT_FROZEN: u8 = 1 << 1;
+pub const NON_TRANSFERABLE: u8 = 1 << 2;
+pub const FREEZE_AUTHORITY: u8 = 1 << 3;
+pub const CLOSE_AUTHORITY: u8 = 1 << 4;
+
+/// Risky features present on a mint, as a set of the flags above.
+pub fn flags(mint: &AccountInfo) -> Result<u8> {
+    let data = mint.try_borrow_data()?;
+
+    // SPL Token mints unpack as a Token-2022 mint without extensions
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    let mut flags = match mint.base.freeze_authority.is_some() {
+        true => FREEZE_AUTHORITY,
+        false => 0,
+    };
+
+    if mint
+        .get_extension::<PermanentDelegate>()
+        .is_ok_and(|extension| Option::<Pubkey>::from(extension.delegate).is_some())
+    {
+        flags |= PERMANENT_DELEGATE;
+    }
+
+    if mint
+        .get_extension::<DefaultAccountState>()
+        .is_ok_and(|extension| extension.state == AccountState::Frozen as u8)
+    {
+        flags |= DEFAULT_FROZEN;
+    }
+
+    if mint.get_extension::<NonTransferable>().is_ok() {
+        flags |= NON_TRANSFERABLE;
+    }
+
+    if mint
+        .get_extension::<MintCloseAuthority>()
+        .is_ok_and(|extension| Option::<Pubkey>::from(extension.close_authority).is_some())
+    {
+        flags |= CLOSE_AUTHORITY;
+    }
+
+    Ok(flags)
+}
+
+/// Fails with the error of the first feature present in `flags` but not in `allowed`.
+pub fn check(flags: u8, allowed: u8) -> Result<()> {
+    let denied = flags & !allowed;
+
+    require!(denied & PERMANENT_DELEGATE == 0, EscrowErrors::PermanentDelegateNotAllowed);
+    require!(denied & DEFAULT_FROZEN == 0, EscrowErrors::DefaultFrozenNotAllowed);
+    require!(denied & NON_TRANSFERABLE == 0, EscrowErrors::NonTransferableNotAllowed);
+    require!(denied & FREEZE_AUTHORITY == 0, EscrowErrors::FreezeAuthorityNotAllowed);
+    require!(denied & CLOSE_AUTHORITY == 0, EscrowErrors::CloseAuthorityNotAllowed);
+
+    Ok(())
+}
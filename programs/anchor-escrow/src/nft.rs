@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{metadata::MetadataAccount, token_interface::Mint};
+
+/// A mint counts as an NFT when it has no decimals and a supply of exactly one.
+pub fn is_nft(mint: &Mint) -> bool {
+    mint.decimals == 0 && mint.supply == 1
+}
+
+/// Whether the metadata of a mint shows a verified membership in `collection`.
+pub fn in_collection(metadata: &MetadataAccount, collection: &Pubkey) -> bool {
+    metadata
+        .collection
+        .as_ref()
+        .is_some_and(|membership| membership.verified && membership.key == *collection)
+}
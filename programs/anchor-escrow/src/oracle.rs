@@ -0,0 +1,96 @@
+use anchor_lang::{prelude::*, solana_program::pubkey};
+
+use crate::{Config, EscrowErrors};
+
+// Pyth price updates are read straight from their on-chain layout: the SDK crate
+// pins an older Solana version than the one this program builds against.
+
+/// Pyth receiver program, owner of every `PriceUpdateV2` account on mainnet-beta and
+/// devnet, the push oracle feeds included.
+pub const PYTH_RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Pyth push oracle program, whose feed accounts are kept updated by Pyth.
+pub const PYTH_PUSH_ORACLE_ID: Pubkey = pubkey!("pythWSnswVUd12oZpeFP8e9CVaEqJg25g1Vtc2biRsT");
+
+const PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+const VERIFICATION_FULL: u8 = 1;
+
+// Offsets past a `Full` verification level, the only one accepted: a `Partial` one
+// carries its number of signatures and shifts the price message by a byte.
+const VERIFICATION_LEVEL_OFFSET: usize = 40;
+const FEED_ID_OFFSET: usize = 41;
+const PRICE_OFFSET: usize = 73;
+const CONF_OFFSET: usize = 81;
+const EXPO_OFFSET: usize = 89;
+const PUBLISH_TIME_OFFSET: usize = 93;
+const PRICE_UPDATE_LEN: usize = 133;
+
+/// Price of a Pyth feed: `price * 10^expo` units of the quote per whole unit of the
+/// base, give or take `conf * 10^expo`.
+pub struct PythPrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    data[offset..offset + N].try_into().unwrap()
+}
+
+impl PythPrice {
+    /// Reads a fully verified `PriceUpdateV2` of the feed `feed_id`.
+    pub fn load(price_update: &AccountInfo, feed_id: &[u8; 32]) -> Result<Self> {
+        require_keys_eq!(*price_update.owner, PYTH_RECEIVER_ID, EscrowErrors::InvalidPriceFeed);
+
+        let data = price_update.try_borrow_data()?;
+        require_gte!(data.len(), PRICE_UPDATE_LEN, EscrowErrors::InvalidPriceFeed);
+        require!(
+            data[..8] == PRICE_UPDATE_DISCRIMINATOR
+                && data[VERIFICATION_LEVEL_OFFSET] == VERIFICATION_FULL
+                && read::<32>(&data, FEED_ID_OFFSET) == *feed_id,
+            EscrowErrors::InvalidPriceFeed
+        );
+
+        Ok(Self {
+            price: i64::from_le_bytes(read(&data, PRICE_OFFSET)),
+            conf: u64::from_le_bytes(read(&data, CONF_OFFSET)),
+            expo: i32::from_le_bytes(read(&data, EXPO_OFFSET)),
+            publish_time: i64::from_le_bytes(read(&data, PUBLISH_TIME_OFFSET)),
+        })
+    }
+
+    /// Rejects a price older than `max_age` seconds, or one whose confidence interval
+    /// is wider than `max_conf_bps` of the price.
+    pub fn check(&self, now: i64, max_age: u32, max_conf_bps: u16) -> Result<()> {
+        require!(now.saturating_sub(self.publish_time) <= max_age as i64, EscrowErrors::StalePrice);
+        require!(self.price > 0, EscrowErrors::InvalidPriceFeed);
+        require!(
+            self.conf as u128 * Config::MAX_BPS as u128 <= self.price as u128 * max_conf_bps as u128,
+            EscrowErrors::PriceUncertain
+        );
+        Ok(())
+    }
+
+    /// Amount of mint B worth `amount_a` of mint A, moved by `spread_bps` and rounded
+    /// up in favour of the maker.
+    pub fn quote(&self, amount_a: u64, decimals_a: u8, decimals_b: u8, spread_bps: i16) -> Result<u64> {
+        let exponent = self.expo + decimals_b as i32 - decimals_a as i32;
+        let scale = 10u128
+            .checked_pow(exponent.unsigned_abs())
+            .ok_or(EscrowErrors::MathOverflow)?;
+
+        let mut numerator = (amount_a as u128)
+            .checked_mul(self.price as u128)
+            .and_then(|value| value.checked_mul((Config::MAX_BPS as i32 + spread_bps as i32) as u128))
+            .ok_or(EscrowErrors::MathOverflow)?;
+        let mut denominator = Config::MAX_BPS as u128;
+
+        match exponent >= 0 {
+            true => numerator = numerator.checked_mul(scale).ok_or(EscrowErrors::MathOverflow)?,
+            false => denominator = denominator.checked_mul(scale).ok_or(EscrowErrors::MathOverflow)?,
+        }
+
+        u64::try_from(numerator.div_ceil(denominator)).map_err(|_| EscrowErrors::MathOverflow.into())
+    }
+}
@@ -1,5 +1,9 @@
 use anchor_lang::prelude::*;
 
+pub use anchor_spl::token::spl_token::native_mint::ID as NATIVE_MINT;
+
+use crate::{merkle, EscrowErrors};
+
 #[account]
 #[derive(InitSpace)]
 pub struct Escrow {
@@ -7,7 +11,431 @@ pub struct Escrow {
     pub maker: Pubkey,
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
+    pub amount: u64,
     pub receive: u64,
+    /// Whether `receive` is what the maker ends up with after mint B transfer fees,
+    /// rather than what the taker sends.
+    pub net_receive: bool,
+    /// Unix timestamp from which the Escrow can be taken.
+    pub start_at: i64,
+    pub expiry: Expiry,
+    pub taker: Option<Pubkey>,
+    pub allowlist: Option<[u8; 32]>,
+    pub nft: NftTerms,
+    pub auction: Option<DutchAuction>,
+    pub english: Option<EnglishAuction>,
+    pub oracle: Option<OracleTerms>,
+    pub bump: u8,
+}
+
+/// A moment chosen by the maker, either as a Unix timestamp or as seconds after the
+/// instruction setting it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum TimeSpec {
+    At(i64),
+    After(u64),
+}
+
+impl TimeSpec {
+    pub fn resolve(&self, now: i64) -> Result<i64> {
+        match *self {
+            TimeSpec::At(timestamp) => Ok(timestamp),
+            TimeSpec::After(seconds) => i64::try_from(seconds)
+                .ok()
+                .and_then(|seconds| now.checked_add(seconds))
+                .ok_or(EscrowErrors::MathOverflow.into()),
+        }
+    }
+
+    /// Resolves when a staged order becomes fillable: at `start_at`, which must come
+    /// before a timestamp expiry, or right away without one.
+    pub fn resolve_start(start_at: Option<TimeSpec>, expiry: Expiry, now: i64) -> Result<i64> {
+        let Some(start_at) = start_at else {
+            return Ok(now);
+        };
+
+        let start_at = start_at.resolve(now)?;
+        if let Expiry::Timestamp(expiry) = expiry {
+            require_gt!(expiry, start_at, EscrowErrors::InvalidStartTime);
+        }
+
+        Ok(start_at)
+    }
+}
+
+/// Deadline after which an Escrow can no longer be taken.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum Expiry {
+    Never,
+    Timestamp(i64),
+    Slot(u64),
+}
+
+impl Expiry {
+    pub fn has_passed(&self, clock: &Clock) -> bool {
+        match *self {
+            Expiry::Never => false,
+            Expiry::Timestamp(timestamp) => clock.unix_timestamp > timestamp,
+            Expiry::Slot(slot) => clock.slot > slot,
+        }
+    }
+}
+
+/// Expiry chosen by the maker: a Unix time, a slot height, or none for an Escrow
+/// good until cancelled.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum ExpiryMode {
+    Never,
+    Timestamp(TimeSpec),
+    Slot(u64),
+}
+
+impl ExpiryMode {
+    pub fn resolve(&self, now: i64) -> Result<Expiry> {
+        Ok(match *self {
+            ExpiryMode::Never => Expiry::Never,
+            ExpiryMode::Timestamp(timestamp) => Expiry::Timestamp(timestamp.resolve(now)?),
+            ExpiryMode::Slot(slot) => Expiry::Slot(slot),
+        })
+    }
+}
+
+/// Sides of an Escrow that must be a single NFT. With `collection_b`, mint B is a
+/// verified Metaplex collection: any NFT of it pays one unit of `receive`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct NftTerms {
+    pub nft_a: bool,
+    pub nft_b: bool,
+    pub collection_b: bool,
+}
+
+/// Dutch auction schedule of an Escrow: the price of everything left in the vault
+/// starts at `start_receive` and decays to the Escrow's `receive` between
+/// `start_time` and `end_time`, continuously or by a drop every `step` seconds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct DutchAuction {
+    pub start_receive: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub step: i64,
+}
+
+impl DutchAuction {
+    pub fn check(&self, floor: u64) -> Result<()> {
+        require!(
+            self.start_receive >= floor && self.start_time < self.end_time && self.step >= 0,
+            EscrowErrors::InvalidAuction
+        );
+        Ok(())
+    }
+
+    /// Price at `now` of an auction decaying to `floor`.
+    pub fn price(&self, floor: u64, now: i64) -> u64 {
+        if now <= self.start_time {
+            return self.start_receive;
+        }
+
+        if now >= self.end_time {
+            return floor;
+        }
+
+        let mut elapsed = now - self.start_time;
+        if self.step > 0 {
+            elapsed -= elapsed % self.step;
+        }
+
+        let decay = (self.start_receive.saturating_sub(floor) as u128) * (elapsed as u128)
+            / ((self.end_time - self.start_time) as u128);
+
+        self.start_receive - decay as u64
+    }
+}
+
+/// Terms of an English auction chosen by the maker at `make`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct EnglishTerms {
+    /// Minimum raise over the top bid, in basis points.
+    pub min_increment_bps: u16,
+    /// A bid landing less than this many seconds before expiry pushes expiry back to
+    /// this many seconds after the bid.
+    pub extension: i64,
+}
+
+/// English auction state of an Escrow: bids of mint B are locked in a bid vault and
+/// `receive` is the reserve price the first bid must reach.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct EnglishAuction {
+    pub terms: EnglishTerms,
+    /// Paid the rent of the bid vault with the first bid, refunded at settlement.
+    pub first_bidder: Option<Pubkey>,
+    pub top_bidder: Option<Pubkey>,
+    /// What the top bid left in the bid vault, after mint B transfer fees.
+    pub top_bid: u64,
+}
+
+impl EnglishAuction {
+    /// Lowest bid outbidding the top bid, or the reserve for the first bid.
+    pub fn min_bid(&self, reserve: u64) -> Result<u64> {
+        if self.top_bidder.is_none() {
+            return Ok(reserve);
+        }
+
+        let increment = (self.top_bid as u128 * self.terms.min_increment_bps as u128)
+            .div_ceil(Config::MAX_BPS as u128)
+            .max(1);
+
+        u64::try_from(self.top_bid as u128 + increment).map_err(|_| EscrowErrors::MathOverflow.into())
+    }
+}
+
+/// Oracle pegging of an Escrow: takes pay the value of what is left in the vault at
+/// the price of mint A in mint B of the Pyth feed `feed_id`, moved by `spread_bps`,
+/// and never less than the Escrow's `receive`. Any fully verified price update of
+/// the feed is accepted, from its push oracle account or posted by the taker.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct OracleTerms {
+    pub feed_id: [u8; 32],
+    /// Premium over the feed price, or a discount when negative, in basis points.
+    pub spread_bps: i16,
+    /// Oldest accepted price, in seconds.
+    pub max_age: u32,
+    /// Widest accepted confidence interval, in basis points of the price.
+    pub max_conf_bps: u16,
+}
+
+impl OracleTerms {
+    pub fn check(&self) -> Result<()> {
+        require!(
+            self.spread_bps > -(Config::MAX_BPS as i16) && self.max_conf_bps <= Config::MAX_BPS,
+            EscrowErrors::InvalidOracleTerms
+        );
+        Ok(())
+    }
+}
+
+/// Scales `value` from an offered amount of mint A to another, rounding up so the
+/// maker never ends up selling at a lower price.
+fn scale_up(value: u64, from_amount: u64, to_amount: u64) -> Result<u64> {
+    require!(from_amount > 0, EscrowErrors::InvalidAmount);
+
+    let value = (value as u128)
+        .checked_mul(to_amount as u128)
+        .ok_or(EscrowErrors::MathOverflow)?
+        .div_ceil(from_amount as u128);
+
+    u64::try_from(value).map_err(|_| EscrowErrors::MathOverflow.into())
+}
+
+impl Escrow {
+    /// Legs quoted in the wrapped SOL mint are settled in native lamports, with
+    /// mint A held directly by the Escrow account instead of a vault.
+    pub fn is_native_a(&self) -> bool {
+        self.mint_a == NATIVE_MINT
+    }
+
+    pub fn is_native_b(&self) -> bool {
+        self.mint_b == NATIVE_MINT
+    }
+
+    /// Native SOL held by an Escrow account on top of its rent-exempt minimum.
+    pub fn native_balance(info: &AccountInfo) -> Result<u64> {
+        let rent = Rent::get()?.minimum_balance(info.data_len());
+        Ok(info.lamports().saturating_sub(rent))
+    }
+
+    /// Scales `receive` from an offered amount of mint A to another, rounding up so
+    /// the maker never ends up selling at a lower price.
+    pub fn scaled_receive(&self, from_amount: u64, to_amount: u64) -> Result<u64> {
+        scale_up(self.receive, from_amount, to_amount)
+    }
+
+    /// Scales the price of the Escrow from an offered amount of mint A to another,
+    /// together with the start price of its Dutch auction. Fails if an ask for a
+    /// single NFT would end up priced at more than one.
+    pub fn rescale(&mut self, from_amount: u64, to_amount: u64) -> Result<()> {
+        self.receive = self.scaled_receive(from_amount, to_amount)?;
+        // An NFT ask stays priced at one NFT, as on make
+        require!(!self.nft.nft_b || self.nft.collection_b || self.receive == 1, EscrowErrors::InvalidNftTerms);
+
+        if let Some(auction) = self.auction.as_mut() {
+            auction.start_receive = scale_up(auction.start_receive, from_amount, to_amount)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether an English auction Escrow holds a bid, which locks it until settled.
+    pub fn has_bids(&self) -> bool {
+        self.english.is_some_and(|auction| auction.top_bidder.is_some())
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.taker.is_none() && self.allowlist.is_none()
+    }
+
+    /// Whether `mint` can pay the Escrow: the pinned mint B, or any mint of a collection bid,
+    /// whose membership is checked against its metadata.
+    pub fn accepts_mint_b(&self, mint: &Pubkey) -> bool {
+        self.nft.collection_b || self.mint_b == *mint
+    }
+
+    pub fn is_allowed(&self, taker: &Pubkey, proof: &[[u8; 32]]) -> bool {
+        self.is_public()
+            || self.taker == Some(*taker)
+            || self
+                .allowlist
+                .is_some_and(|root| merkle::verify(proof, root, merkle::leaf(taker.as_ref())))
+    }
+}
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub fee_bps: u16,
+    pub referral_bps: u16,
+    pub bump: u8,
+}
+
+impl Config {
+    pub const MAX_BPS: u16 = 10_000;
+    /// Cap on `fee_bps`, bounding what an authority can take from live Escrows.
+    pub const MAX_FEE_BPS: u16 = 1_000;
+
+    /// Splits the fee owed on `amount` into the protocol share and the referrer share,
+    /// the latter being a cut of the fee and only paid when a referrer is present.
+    pub fn split_fee(&self, amount: u64, with_referrer: bool) -> Result<(u64, u64)> {
+        let fee = (amount as u128)
+            .checked_mul(self.fee_bps as u128)
+            .ok_or(EscrowErrors::MathOverflow)?
+            / Self::MAX_BPS as u128;
+
+        let referral_fee = match with_referrer {
+            true => fee
+                .checked_mul(self.referral_bps as u128)
+                .ok_or(EscrowErrors::MathOverflow)?
+                / Self::MAX_BPS as u128,
+            false => 0,
+        };
+
+        Ok(((fee - referral_fee) as u64, referral_fee as u64))
+    }
+}
+/// Legs a Bundle can hold on each side.
+pub const MAX_BUNDLE_LEGS: usize = 4;
+
+/// A basket swap: the maker deposits every `give` leg into its own vault and asks
+/// for every `receive` leg, all settled by a single `take_bundle`.
+#[account]
+#[derive(InitSpace)]
+pub struct Bundle {
+    pub seed: u64,
+    pub maker: Pubkey,
+    #[max_len(MAX_BUNDLE_LEGS)]
+    pub give: Vec<BundleLeg>,
+    #[max_len(MAX_BUNDLE_LEGS)]
+    pub receive: Vec<BundleLeg>,
+    /// Unix timestamp from which the Bundle can be taken.
+    pub start_at: i64,
+    pub expiry: Expiry,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct BundleLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+impl Bundle {
+    /// Checks one side of a Bundle: between one and `MAX_BUNDLE_LEGS` legs of distinct
+    /// mints, none of them empty.
+    pub fn check_legs(legs: &[BundleLeg]) -> Result<()> {
+        require!((1..=MAX_BUNDLE_LEGS).contains(&legs.len()), EscrowErrors::InvalidBundle);
+
+        for (i, leg) in legs.iter().enumerate() {
+            require!(leg.amount > 0, EscrowErrors::InvalidBundle);
+            require!(legs[..i].iter().all(|other| other.mint != leg.mint), EscrowErrors::InvalidBundle);
+        }
+
+        Ok(())
+    }
+}
+
+/// An order the maker signs off-chain with ed25519 instead of making an Escrow. A
+/// taker fills it whole with `fill_signed_order`, which pulls mint A from the maker's
+/// token account through its delegation to the program's delegate account. Each
+/// nonce fills at most once, tracked by the maker's OrderNonce.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SignedOrder {
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    /// Unix timestamp after which the order can't be filled.
     pub expiry: i64,
+    pub taker: Option<Pubkey>,
+    pub nonce: u64,
+}
+
+impl SignedOrder {
+    /// Prefix of every signed order message, so makers never sign something that
+    /// could be replayed as another kind of message.
+    pub const DOMAIN: &'static [u8] = b"anchor-escrow:signed-order";
+
+    /// Bytes the maker signs: the domain, this program's ID and the serialized order.
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = [Self::DOMAIN, crate::ID.as_ref()].concat();
+        self.serialize(&mut message).unwrap();
+        message
+    }
+}
+
+/// Nonces an OrderNonce tracks individually, from its `min_nonce` up.
+pub const ORDER_NONCE_WINDOW: u64 = 1_024;
+
+/// Replay protection for a maker's signed orders. Every nonce below `min_nonce` is
+/// cancelled, which only the maker can do; the bitmap
+/// records the fills of the `ORDER_NONCE_WINDOW` nonces from there, each at bit
+/// `nonce % ORDER_NONCE_WINDOW`.
+#[account]
+#[derive(InitSpace)]
+pub struct OrderNonce {
+    pub maker: Pubkey,
+    pub min_nonce: u64,
+    pub bitmap: [u64; (ORDER_NONCE_WINDOW / 64) as usize],
     pub bump: u8,
-}
\ No newline at end of file
+}
+
+impl OrderNonce {
+    fn bit(nonce: u64) -> (usize, u64) {
+        let index = nonce % ORDER_NONCE_WINDOW;
+        ((index / 64) as usize, 1 << (index % 64))
+    }
+
+    /// Marks `nonce` filled. A nonce past the window can't be filled until the maker
+    /// cancels up to it, so makers should keep their open orders within
+    /// `ORDER_NONCE_WINDOW` consecutive nonces.
+    pub fn use_nonce(&mut self, nonce: u64) -> Result<()> {
+        require_gte!(nonce, self.min_nonce, EscrowErrors::OrderCancelled);
+        require!(nonce - self.min_nonce < ORDER_NONCE_WINDOW, EscrowErrors::InvalidNonce);
+
+        let (word, mask) = Self::bit(nonce);
+        require!(self.bitmap[word] & mask == 0, EscrowErrors::OrderNonceUsed);
+        self.bitmap[word] |= mask;
+
+        Ok(())
+    }
+
+    /// Spends every nonce below `min_nonce`, clearing their bits for reuse by the
+    /// nonces entering the window.
+    pub fn advance(&mut self, min_nonce: u64) {
+        let end = min_nonce.min(self.min_nonce.saturating_add(ORDER_NONCE_WINDOW));
+        for nonce in self.min_nonce..end {
+            let (word, mask) = Self::bit(nonce);
+            self.bitmap[word] &= !mask;
+        }
+        self.min_nonce = min_nonce;
+    }
+}
@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    token_2022::{
+        self,
+        spl_token_2022::{
+            extension::{
+                transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+                BaseStateWithExtensions, StateWithExtensions,
+            },
+            state::{Account, Mint},
+        },
+    },
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+};
+
+use crate::EscrowErrors;
+
+// Token-2022 mints with the `TransferFeeConfig` extension withhold part of every
+// transfer in the destination account. Every other mint behaves as if the fee were 0.
+
+fn fee_config(mint: &AccountInfo) -> Result<Option<TransferFeeConfig>> {
+    if *mint.owner != token_2022::ID {
+        return Ok(None);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    Ok(mint.get_extension::<TransferFeeConfig>().ok().copied())
+}
+
+/// Fee withheld when sending `amount` of `mint`.
+pub fn fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    match fee_config(mint)? {
+        Some(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(EscrowErrors::MathOverflow.into()),
+        None => Ok(0),
+    }
+}
+
+/// Fee to add on top of `amount` of `mint` so that the recipient gets exactly `amount`.
+pub fn inverse_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    match fee_config(mint)? {
+        Some(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(EscrowErrors::MathOverflow.into()),
+        None => Ok(0),
+    }
+}
+
+/// Moves the fees withheld in a token account back to its mint, since Token-2022
+/// refuses to close an account still holding withheld fees.
+pub fn harvest<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+) -> Result<()> {
+    if *account.owner != token_2022::ID {
+        return Ok(());
+    }
+
+    let withheld = {
+        let data = account.try_borrow_data()?;
+        let account = StateWithExtensions::<Account>::unpack(&data)?;
+        account
+            .get_extension::<TransferFeeAmount>()
+            .map_or(0, |fees| u64::from(fees.withheld_amount))
+    };
+
+    if withheld == 0 {
+        return Ok(());
+    }
+
+    let ctx = CpiContext::new(
+        token_program.clone(),
+        HarvestWithheldTokensToMint {
+            token_program_id: token_program,
+            mint,
+        },
+    );
+
+    harvest_withheld_tokens_to_mint(ctx, vec![account])
+}
@@ -0,0 +1,183 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{BundleLeg, ExpiryMode, TimeSpec}}, helpers::{*, spl_token_2022_helpers::*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Testing Bundle escrows: a maker depositing two mints into per-mint vaults and
+// asking for two other mints, settled in a single take_bundle.
+
+struct BundleSetup {
+    context: ProgramTestContext,
+    maker: Keypair,
+    taker: Keypair,
+    fee_recipient: Pubkey,
+    seed: u64,
+    bundle: Pubkey,
+    give: Vec<BundleLeg>,
+    receive: Vec<BundleLeg>,
+}
+
+// Setting up the mints of both sides, the maker holding the `give` mints and the
+// taker the `receive` mints. The protocol takes a 1% fee.
+
+async fn setup() -> BundleSetup {
+    let mut test = program_test(400_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let seed: u64 = rand::thread_rng().gen();
+
+    let give: Vec<BundleLeg> = [100, 200].map(|amount| BundleLeg { mint: Keypair::new().pubkey(), amount }).to_vec();
+    let receive: Vec<BundleLeg> = [300, 400].map(|amount| BundleLeg { mint: Keypair::new().pubkey(), amount }).to_vec();
+
+    for leg in &give {
+        add_mint(&mut test, leg.mint, 100_000);
+        add_token_account(&mut test, leg.mint, maker.pubkey(), 1_000);
+    }
+
+    for leg in &receive {
+        add_mint(&mut test, leg.mint, 100_000);
+        add_token_account(&mut test, leg.mint, taker.pubkey(), 1_000);
+    }
+
+    // Setup config account with a 1% fee
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 100, 0);
+
+    let (bundle, _) = Pubkey::find_program_address(
+        &[b"bundle", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()],
+        &anchor_escrow::id(),
+    );
+
+    let mut context = test.start_with_context().await;
+
+    // Airdrop SOL to maker and taker
+    airdrop(&mut context.banks_client, &context.payer, &maker.pubkey(), LAMPORTS_PER_SOL).await.unwrap();
+    airdrop(&mut context.banks_client, &context.payer, &taker.pubkey(), LAMPORTS_PER_SOL).await.unwrap();
+
+    BundleSetup { context, maker, taker, fee_recipient, seed, bundle, give, receive }
+}
+
+async fn make(setup: &mut BundleSetup, start_at: Option<TimeSpec>) {
+    let instruction = make_bundle(anchor_escrow::id(), spl_token::id(), setup.maker.pubkey(), setup.seed, setup.give.clone(), setup.receive.clone(), ExpiryMode::Timestamp(TimeSpec::After(3_600)), start_at);
+    process(&mut setup.context, instruction, &[&setup.maker]).await.unwrap();
+}
+
+// Making a Bundle and taking it: the maker ends up with both receive legs, less the
+// protocol fee, and the taker with both vaults, which are closed together with the
+// Bundle.
+
+#[tokio::test]
+async fn successful_make_and_take_bundle() {
+    let mut setup = setup().await;
+    make(&mut setup, None).await;
+
+    // Verify the vaults
+    for leg in &setup.give {
+        assert_eq!(balance(&mut setup.context, &setup.bundle, &leg.mint).await, leg.amount);
+    }
+
+    let instruction = take_bundle(anchor_escrow::id(), spl_token::id(), setup.taker.pubkey(), setup.maker.pubkey(), setup.bundle, setup.fee_recipient, &setup.give, &setup.receive);
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&setup.context.payer.pubkey()));
+    transaction.sign(&[&setup.context.payer, &setup.taker], setup.context.banks_client.get_latest_blockhash().await.unwrap());
+
+    let result = setup.context.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    assert!(result.result.is_ok());
+    assert!(emits_event_cpi(&result.metadata.unwrap().log_messages), "Take should emit its event through a CPI");
+
+    for leg in setup.receive.clone() {
+        assert_eq!(balance(&mut setup.context, &setup.maker.pubkey(), &leg.mint).await, leg.amount * 99 / 100, "Maker should receive every receive leg less the fee");
+
+        assert_eq!(balance(&mut setup.context, &setup.fee_recipient, &leg.mint).await, leg.amount / 100, "Fee recipient should receive 1% of every receive leg");
+    }
+
+    for leg in setup.give.clone() {
+        assert_eq!(balance(&mut setup.context, &setup.taker.pubkey(), &leg.mint).await, leg.amount, "Taker should receive every give leg");
+
+        let vault = spl_associated_token_account::get_associated_token_address(&setup.bundle, &leg.mint);
+        assert!(setup.context.banks_client.get_account(vault).await.unwrap().is_none(), "Vault should be closed");
+    }
+
+    let bundle_account = setup.context.banks_client.get_account(setup.bundle).await.unwrap();
+    assert!(bundle_account.is_none(), "Bundle account should be closed");
+}
+
+// Trying to make a Bundle giving the same mint twice.
+
+#[tokio::test]
+async fn make_bundle_error_invalid_bundle() {
+    let mut setup = setup().await;
+    let give = vec![setup.give[0], setup.give[0]];
+
+    let instruction = make_bundle(anchor_escrow::id(), spl_token::id(), setup.maker.pubkey(), setup.seed, give, setup.receive.clone(), ExpiryMode::Timestamp(TimeSpec::After(3_600)), None);
+    let result = process(&mut setup.context, instruction, &[&setup.maker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidBundle);
+}
+
+// Trying to make a Bundle giving a mint with a transfer fee.
+
+#[tokio::test]
+async fn make_bundle_error_unsupported_mint() {
+    let mut setup = setup().await;
+
+    let mint = create_mint_with_transfer_fee(&mut setup.context.banks_client, &setup.context.payer, 100, u64::MAX).await.unwrap();
+    create_and_mint_to_token_2022_account(&mut setup.context.banks_client, mint, &setup.context.payer, setup.maker.pubkey(), 1_000).await.unwrap();
+
+    let give = vec![BundleLeg { mint, amount: 100 }];
+    let instruction = make_bundle(anchor_escrow::id(), spl_token_2022::id(), setup.maker.pubkey(), setup.seed, give, setup.receive.clone(), ExpiryMode::Timestamp(TimeSpec::After(3_600)), None);
+    let result = process(&mut setup.context, instruction, &[&setup.maker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::UnsupportedBundleMint);
+}
+
+// Trying to take a Bundle passing the receive legs in the wrong order.
+
+#[tokio::test]
+async fn take_bundle_error_account_mismatch() {
+    let mut setup = setup().await;
+    make(&mut setup, None).await;
+
+    let receive = vec![setup.receive[1], setup.receive[0]];
+    let instruction = take_bundle(anchor_escrow::id(), spl_token::id(), setup.taker.pubkey(), setup.maker.pubkey(), setup.bundle, setup.fee_recipient, &setup.give, &receive);
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::BundleAccountMismatch);
+
+    let bundle_account = setup.context.banks_client.get_account(setup.bundle).await.unwrap();
+    assert!(bundle_account.is_some(), "Bundle account should still exist");
+}
+
+// Trying to take a Bundle before its start time.
+
+#[tokio::test]
+async fn take_bundle_error_not_started() {
+    let mut setup = setup().await;
+    make(&mut setup, Some(TimeSpec::After(600))).await;
+
+    let instruction = take_bundle(anchor_escrow::id(), spl_token::id(), setup.taker.pubkey(), setup.maker.pubkey(), setup.bundle, setup.fee_recipient, &setup.give, &setup.receive);
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowNotStarted);
+}
+
+// Refunding a Bundle: every vault goes back to the maker.
+
+#[tokio::test]
+async fn successful_refund_bundle() {
+    let mut setup = setup().await;
+    make(&mut setup, None).await;
+
+    let instruction = refund_bundle(anchor_escrow::id(), spl_token::id(), setup.maker.pubkey(), setup.bundle, &setup.give);
+    process(&mut setup.context, instruction, &[&setup.maker]).await.unwrap();
+
+    for leg in setup.give.clone() {
+        assert_eq!(balance(&mut setup.context, &setup.maker.pubkey(), &leg.mint).await, 1_000, "Maker should get every give leg back");
+    }
+
+    let bundle_account = setup.context.banks_client.get_account(setup.bundle).await.unwrap();
+    assert!(bundle_account.is_none(), "Bundle account should be closed");
+}
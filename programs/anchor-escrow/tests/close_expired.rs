@@ -0,0 +1,139 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, Expiry}}, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Setting up an Escrow offering 100 tokens of mint A directly in the Bank.
+
+fn setup_escrow(test: &mut ProgramTest, maker: &Keypair, mint_a: Pubkey, expiry: i64) -> Pubkey {
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b: Keypair::new().pubkey(),
+        amount: 100,
+        receive: 300,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Timestamp(expiry),
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(test, mint_a, 100_000);
+    add_token_account(test, mint_a, escrow_pubkey, 100);
+
+    escrow_pubkey
+}
+
+// Closing an expired Escrow from a third party: mint A and the rent of the Escrow
+// go back to the maker, the rent of the vault pays for the cranker's work.
+
+#[tokio::test]
+async fn successful_close_expired() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let cranker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let escrow_pubkey = setup_escrow(&mut test, &maker, mint_a, 0);
+    let vault = spl_associated_token_account::get_associated_token_address(&escrow_pubkey, &mint_a);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to cranker
+    let _ = airdrop(&mut banks_client, &payer, &cranker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let escrow_lamports = banks_client.get_balance(escrow_pubkey).await.unwrap();
+    let vault_lamports = banks_client.get_balance(vault).await.unwrap();
+    let ata_rent = banks_client.get_rent().await.unwrap().minimum_balance(spl_token::state::Account::LEN);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[close_expired(
+            anchor_escrow::id(),
+            spl_token::id(),
+            cranker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            escrow_pubkey,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &cranker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let maker_ata_a = spl_associated_token_account::get_associated_token_address(&maker.pubkey(), &mint_a);
+    assert_eq!(get_token_balance(&mut banks_client, maker_ata_a).await.unwrap(), 100, "Maker should receive the tokens of the vault");
+
+    let maker_lamports = banks_client.get_balance(maker.pubkey()).await.unwrap();
+    assert_eq!(maker_lamports, escrow_lamports, "Maker should receive the rent of the Escrow account");
+
+    let cranker_lamports = banks_client.get_balance(cranker.pubkey()).await.unwrap();
+    assert_eq!(cranker_lamports, LAMPORTS_PER_SOL - ata_rent + vault_lamports, "Cranker should receive the rent of the vault");
+
+    assert!(banks_client.get_account(vault).await.unwrap().is_none(), "Vault should be closed");
+    assert!(banks_client.get_account(escrow_pubkey).await.unwrap().is_none(), "Escrow account should be closed");
+}
+
+// Trying to close an Escrow that is still live.
+
+#[tokio::test]
+async fn close_expired_error_escrow_not_expired() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let cranker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let escrow_pubkey = setup_escrow(&mut test, &maker, mint_a, i64::MAX);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to cranker
+    let _ = airdrop(&mut banks_client, &payer, &cranker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[close_expired(
+            anchor_escrow::id(),
+            spl_token::id(),
+            cranker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            escrow_pubkey,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &cranker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowNotExpired);
+}
@@ -0,0 +1,188 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::Config}, anchor_lang::AccountDeserialize, helpers::*, solana_program_test::*, solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Testing the InitConfig and UpdateConfig instructions, verifying the Config
+// state after each of them.
+
+#[tokio::test]
+async fn successful_init_and_update_config() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let authority = Keypair::new();
+    let fee_recipient = Keypair::new().pubkey();
+    let new_fee_recipient = Keypair::new().pubkey();
+    add_program_data(&mut test, authority.pubkey());
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to authority
+    let _ = airdrop(&mut banks_client, &payer, &authority.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[init_config(
+            anchor_escrow::id(),
+            authority.pubkey(),
+            fee_recipient,
+            50,
+            2_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &authority], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let (config_pubkey, _) = Pubkey::find_program_address(&[b"config"], &anchor_escrow::id());
+    let config_account = banks_client.get_account(config_pubkey).await.unwrap().unwrap();
+    let config = Config::try_deserialize(&mut config_account.data.as_ref()).unwrap();
+
+    assert_eq!(config.authority, authority.pubkey());
+    assert_eq!(config.fee_recipient, fee_recipient);
+    assert_eq!(config.fee_bps, 50);
+    assert_eq!(config.referral_bps, 2_000);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update_config(
+            anchor_escrow::id(),
+            authority.pubkey(),
+            new_fee_recipient,
+            25,
+            0,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &authority], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let config_account = banks_client.get_account(config_pubkey).await.unwrap().unwrap();
+    let config = Config::try_deserialize(&mut config_account.data.as_ref()).unwrap();
+
+    assert_eq!(config.fee_recipient, new_fee_recipient);
+    assert_eq!(config.fee_bps, 25);
+    assert_eq!(config.referral_bps, 0);
+}
+
+// Trying to update the Config with a signer that is not its authority.
+
+#[tokio::test]
+async fn update_config_error_wrong_authority() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let impostor = Keypair::new();
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 50, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update_config(
+            anchor_escrow::id(),
+            impostor.pubkey(),
+            impostor.pubkey(),
+            10_000,
+            0,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &impostor], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert!(result.is_err(), "Only the authority should be able to update the config");
+}
+
+// Trying to initialize the Config with a signer that is not the program's upgrade
+// authority.
+
+#[tokio::test]
+async fn init_config_error_not_upgrade_authority() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let impostor = Keypair::new();
+    add_program_data(&mut test, Keypair::new().pubkey());
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to impostor
+    let _ = airdrop(&mut banks_client, &payer, &impostor.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[init_config(
+            anchor_escrow::id(),
+            impostor.pubkey(),
+            impostor.pubkey(),
+            50,
+            0,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &impostor], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::Unauthorized);
+}
+
+// Trying to initialize the Config with a fee above the cap.
+
+#[tokio::test]
+async fn init_config_error_fee_above_cap() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let authority = Keypair::new();
+    add_program_data(&mut test, authority.pubkey());
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to authority
+    let _ = airdrop(&mut banks_client, &payer, &authority.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[init_config(
+            anchor_escrow::id(),
+            authority.pubkey(),
+            authority.pubkey(),
+            Config::MAX_FEE_BPS + 1,
+            0,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &authority], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidFee);
+}
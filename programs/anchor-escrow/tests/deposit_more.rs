@@ -0,0 +1,167 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, Expiry, NftTerms}}, anchor_lang::AccountDeserialize, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Topping up the vault of a live Escrow, once keeping the receive fixed and once
+// scaling it to keep the same price.
+
+#[tokio::test]
+async fn successful_deposit_more() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(&mut test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b: Keypair::new().pubkey(),
+        amount: 100,
+        receive: 200,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_token_account(&mut test, mint_a, maker.pubkey(), 1_000);
+    let vault = add_token_account(&mut test, mint_a, escrow_pubkey, 100);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    // Top up without touching the receive
+    let mut transaction = Transaction::new_with_payer(
+        &[deposit_more(
+            anchor_escrow::id(),
+            spl_token::id(),
+            maker.pubkey(),
+            mint_a,
+            escrow_pubkey,
+            100,
+            false,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_token_balance(&mut banks_client, vault).await.unwrap(), 200, "Vault should hold 200 tokens of mint A");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow.receive, 200, "Receive should stay fixed");
+
+    // Top up keeping the same price
+    let mut transaction = Transaction::new_with_payer(
+        &[deposit_more(
+            anchor_escrow::id(),
+            spl_token::id(),
+            maker.pubkey(),
+            mint_a,
+            escrow_pubkey,
+            50,
+            true,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_token_balance(&mut banks_client, vault).await.unwrap(), 250, "Vault should hold 250 tokens of mint A");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow.receive, 250, "Receive should scale with the vault");
+}
+
+// Scaling the receive of an ask for a single NFT would price it at several NFTs.
+
+#[tokio::test]
+async fn deposit_more_error_scaled_nft_ask() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(&mut test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b: Keypair::new().pubkey(),
+        amount: 100,
+        receive: 1,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: NftTerms { nft_b: true, ..Default::default() },
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_token_account(&mut test, mint_a, maker.pubkey(), 1_000);
+    add_token_account(&mut test, mint_a, escrow_pubkey, 100);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[deposit_more(
+            anchor_escrow::id(),
+            spl_token::id(),
+            maker.pubkey(),
+            mint_a,
+            escrow_pubkey,
+            100,
+            true,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidNftTerms);
+}
@@ -0,0 +1,165 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{DutchAuction, Escrow, ExpiryMode, TimeSpec}}, anchor_lang::AccountDeserialize, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Testing Dutch auction Escrows: the price of mint B decays from the start price
+// to the floor in `receive`, and takes pay the price of the moment they land.
+
+// Setting up an Escrow of 200 tokens of mint A whose price decays from 1_000 to
+// 200 tokens of mint B over 800 seconds starting now, dropping every `step` seconds.
+
+async fn setup(step: i64) -> EscrowSetup {
+    EscrowSetup::start(program_test(200_000), 200, 200, |escrow, clock| {
+        let now = clock.unix_timestamp;
+        Escrow {
+            auction: Some(DutchAuction { start_receive: 1_000, start_time: now, end_time: now + 800, step }),
+            ..escrow
+        }
+    }).await
+}
+
+async fn maker_balance_b(setup: &mut EscrowSetup) -> u64 {
+    let maker_ata_b = spl_associated_token_account::get_associated_token_address(&setup.maker.pubkey(), &setup.mint_b);
+    get_token_balance(&mut setup.context.banks_client, maker_ata_b).await.unwrap()
+}
+
+// Taking a linear auction halfway through pays half of the decay above the floor.
+
+#[tokio::test]
+async fn successful_take_at_linear_auction_price() {
+    let mut setup = setup(0).await;
+
+    // Move halfway through the auction
+    advance_clock(&mut setup.context, 400).await;
+
+    let instruction = setup.take_instruction(None, 600);
+    process(&mut setup.context, instruction, &[&setup.taker]).await.unwrap();
+
+    assert_eq!(maker_balance_b(&mut setup).await, 600, "Maker should receive the current auction price");
+
+    let escrow_account = setup.context.banks_client.get_account(setup.escrow).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed");
+}
+
+// Taking a stepped auction pays the price of the last drop.
+
+#[tokio::test]
+async fn successful_take_at_stepped_auction_price() {
+    let mut setup = setup(300).await;
+
+    // Move past the first drop only
+    advance_clock(&mut setup.context, 400).await;
+
+    let instruction = setup.take_instruction(None, u64::MAX);
+    process(&mut setup.context, instruction, &[&setup.taker]).await.unwrap();
+
+    assert_eq!(maker_balance_b(&mut setup).await, 700, "Maker should receive the price after one drop");
+}
+
+// Trying to take an auction that has not decayed down to the taker's maximum price.
+
+#[tokio::test]
+async fn take_error_auction_price_above_max_amount_b() {
+    let mut setup = setup(0).await;
+
+    let instruction = setup.take_instruction(None, 500);
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::SlippageExceeded);
+}
+
+// Partially filling an auction: the fill is priced at the current price and the
+// schedule of what is left is scaled down with it.
+
+#[tokio::test]
+async fn successful_take_partial_scales_auction() {
+    let mut setup = setup(0).await;
+
+    // Move halfway through the auction, where everything left costs 600
+    advance_clock(&mut setup.context, 400).await;
+
+    let instruction = take_partial(
+        anchor_escrow::id(),
+        spl_token::id(),
+        spl_token::id(),
+        setup.taker.pubkey(),
+        setup.maker.pubkey(),
+        setup.mint_a,
+        setup.mint_b,
+        setup.escrow,
+        setup.fee_recipient,
+        None,
+        None,
+        None,
+        300,
+        0,
+        u64::MAX,
+        vec![],
+    );
+    process(&mut setup.context, instruction, &[&setup.taker]).await.unwrap();
+
+    let taker_ata_a = spl_associated_token_account::get_associated_token_address(&setup.taker.pubkey(), &setup.mint_a);
+    let taker_balance = get_token_balance(&mut setup.context.banks_client, taker_ata_a).await.unwrap();
+    assert_eq!(taker_balance, 100, "Paying half of the price should release half of mint A");
+
+    let account = setup.context.banks_client.get_account(setup.escrow).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(escrow.amount, 100);
+    assert_eq!(escrow.receive, 100, "Floor should be scaled down to what is left");
+    assert_eq!(escrow.auction.unwrap().start_receive, 500, "Start price should be scaled down to what is left");
+}
+
+// Trying to make an auction starting below its floor.
+
+#[tokio::test]
+async fn make_error_invalid_auction() {
+    let mut test = program_test(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_mint(&mut test, mint_b, 100_000);
+    add_token_account(&mut test, mint_a, maker.pubkey(), 100_000);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            rand::thread_rng().gen(),
+            100,
+            500,
+            false,
+            ExpiryMode::Timestamp(TimeSpec::After(3_600)),
+            None,
+            None,
+            0,
+            Default::default(),
+            Some(DutchAuction { start_receive: 400, start_time: 0, end_time: 3_600, step: 0 }),
+            None,
+            None,
+            None,
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidAuction);
+}
@@ -0,0 +1,270 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{EnglishAuction, EnglishTerms, Escrow, Expiry}}, anchor_lang::AccountDeserialize, helpers::{*, spl_token_helpers::*}, solana_program_test::*, solana_sdk::{
+        instruction::Instruction,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Testing English auction Escrows: bids of mint B are locked in a bid vault, every
+// new top bid refunds the previous one, and the top bidder gets mint A at settlement.
+
+struct AuctionSetup {
+    context: ProgramTestContext,
+    maker: Keypair,
+    alice: Keypair,
+    bob: Keypair,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    escrow: Pubkey,
+    fee_recipient: Pubkey,
+}
+
+// Setting up an Escrow auctioning 200 tokens of mint A for a reserve of 500 tokens
+// of mint B, ending in 1_000 seconds, with 10% minimum raises and a 300 second
+// extension for late bids.
+
+async fn setup() -> AuctionSetup {
+    let EscrowSetup { mut context, maker, mint_a, mint_b, escrow, fee_recipient, .. } =
+        EscrowSetup::start(program_test(200_000), 200, 500, |escrow, clock| Escrow {
+            expiry: Expiry::Timestamp(clock.unix_timestamp + 1_000),
+            english: Some(EnglishAuction {
+                terms: EnglishTerms { min_increment_bps: 1_000, extension: 300 },
+                first_bidder: None,
+                top_bidder: None,
+                top_bid: 0,
+            }),
+            ..escrow
+        }).await;
+
+    // Fund both bidders with mint B and SOL
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+
+    for bidder in [&alice, &bob] {
+        set_token_account(&mut context, mint_b, bidder.pubkey(), 100_000);
+        let _ = airdrop(&mut context.banks_client, &context.payer, &bidder.pubkey(), LAMPORTS_PER_SOL).await;
+    }
+
+    AuctionSetup { context, maker, alice, bob, mint_a, mint_b, escrow, fee_recipient }
+}
+
+async fn place_bid(setup: &mut AuctionSetup, bidder: &Keypair, previous_bidder: Option<Pubkey>, amount: u64) -> Result<(), BanksClientError> {
+    let instruction = bid(
+        anchor_escrow::id(),
+        spl_token::id(),
+        bidder.pubkey(),
+        setup.maker.pubkey(),
+        setup.mint_b,
+        setup.escrow,
+        previous_bidder,
+        amount,
+    );
+    process(&mut setup.context, instruction, &[bidder]).await
+}
+
+fn settle_instruction(setup: &AuctionSetup, winner: Pubkey, first_bidder: Pubkey) -> Instruction {
+    settle_auction(
+        anchor_escrow::id(),
+        spl_token::id(),
+        setup.context.payer.pubkey(),
+        setup.maker.pubkey(),
+        winner,
+        first_bidder,
+        setup.mint_a,
+        setup.mint_b,
+        setup.escrow,
+        setup.fee_recipient,
+    )
+}
+
+async fn fetch_escrow(setup: &mut AuctionSetup) -> Escrow {
+    let account = setup.context.banks_client.get_account(setup.escrow).await.unwrap().unwrap();
+    Escrow::try_deserialize(&mut account.data.as_ref()).unwrap()
+}
+
+// Outbidding the top bidder: raises below 10% are rejected, and a valid raise
+// refunds the previous top bid in full.
+
+#[tokio::test]
+async fn successful_outbid_refunds_previous_bidder() {
+    let mut setup = setup().await;
+    let alice = setup.alice.insecure_clone();
+    let bob = setup.bob.insecure_clone();
+
+    // The first bid must reach the reserve
+    let result = place_bid(&mut setup, &alice, None, 499).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::BidTooLow);
+
+    place_bid(&mut setup, &alice, None, 500).await.unwrap();
+
+    // Raising by less than 10% of the top bid
+    let result = place_bid(&mut setup, &bob, Some(alice.pubkey()), 549).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::BidTooLow);
+
+    let instruction = bid(
+        anchor_escrow::id(),
+        spl_token::id(),
+        bob.pubkey(),
+        setup.maker.pubkey(),
+        setup.mint_b,
+        setup.escrow,
+        Some(alice.pubkey()),
+        550,
+    );
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&setup.context.payer.pubkey()));
+    let blockhash = setup.context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&setup.context.payer, &bob], blockhash);
+
+    let result = setup.context.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    assert!(result.result.is_ok(), "Outbidding by 10% should succeed");
+    assert!(emits_event_cpi(&result.metadata.unwrap().log_messages), "Bid should emit its event through a CPI");
+
+    assert_eq!(balance(&mut setup.context, &alice.pubkey(), &setup.mint_b).await, 100_000, "Alice should get her bid back");
+    assert_eq!(balance(&mut setup.context, &setup.escrow, &setup.mint_b).await, 550, "Bid vault should hold the top bid");
+
+    let escrow = fetch_escrow(&mut setup).await;
+    let auction = escrow.english.unwrap();
+    assert_eq!(auction.top_bidder, Some(bob.pubkey()));
+    assert_eq!(auction.top_bid, 550);
+}
+
+// Outbidding without passing the current top bidder to refund.
+
+#[tokio::test]
+async fn bid_error_bidder_mismatch() {
+    let mut setup = setup().await;
+    let alice = setup.alice.insecure_clone();
+    let bob = setup.bob.insecure_clone();
+
+    place_bid(&mut setup, &alice, None, 500).await.unwrap();
+
+    let result = place_bid(&mut setup, &bob, Some(bob.pubkey()), 600).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::BidderMismatch);
+}
+
+// A bid landing in the last 300 seconds pushes expiry to 300 seconds after the bid.
+
+#[tokio::test]
+async fn successful_late_bid_extends_expiry() {
+    let mut setup = setup().await;
+    let alice = setup.alice.insecure_clone();
+    let Expiry::Timestamp(expiry) = fetch_escrow(&mut setup).await.expiry else { unreachable!() };
+
+    // Move to 100 seconds before expiry
+    advance_clock(&mut setup.context, 900).await;
+
+    place_bid(&mut setup, &alice, None, 500).await.unwrap();
+
+    let escrow = fetch_escrow(&mut setup).await;
+    assert_eq!(escrow.expiry, Expiry::Timestamp(expiry + 200), "Expiry should be extended to 300 seconds after the bid");
+}
+
+// Settling an expired auction: the top bidder gets mint A, the maker the top bid,
+// and the Escrow account is closed. Anyone can settle.
+
+#[tokio::test]
+async fn successful_settle_auction() {
+    let mut setup = setup().await;
+    let alice = setup.alice.insecure_clone();
+
+    place_bid(&mut setup, &alice, None, 500).await.unwrap();
+
+    advance_clock(&mut setup.context, 1_001).await;
+
+    let instruction = settle_instruction(&setup, alice.pubkey(), alice.pubkey());
+    process(&mut setup.context, instruction, &[]).await.unwrap();
+
+    assert_eq!(balance(&mut setup.context, &alice.pubkey(), &setup.mint_a).await, 200, "Winner should receive mint A");
+    assert_eq!(balance(&mut setup.context, &setup.maker.pubkey(), &setup.mint_b).await, 500, "Maker should receive the top bid");
+
+    let escrow_account = setup.context.banks_client.get_account(setup.escrow).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed");
+}
+
+// Settling an auction won by an outbidder: the rent of the bid vault goes back to
+// the first bidder, who paid it.
+
+#[tokio::test]
+async fn successful_settle_refunds_first_bidder() {
+    let mut setup = setup().await;
+    let alice = setup.alice.insecure_clone();
+    let bob = setup.bob.insecure_clone();
+
+    place_bid(&mut setup, &alice, None, 500).await.unwrap();
+    place_bid(&mut setup, &bob, Some(alice.pubkey()), 550).await.unwrap();
+
+    advance_clock(&mut setup.context, 1_001).await;
+
+    // Passing the winner as the first bidder
+    let instruction = settle_instruction(&setup, bob.pubkey(), bob.pubkey());
+    let result = process(&mut setup.context, instruction, &[]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::BidderMismatch);
+
+    let bid_vault = spl_associated_token_account::get_associated_token_address(&setup.escrow, &setup.mint_b);
+    let rent = setup.context.banks_client.get_balance(bid_vault).await.unwrap();
+    let before = setup.context.banks_client.get_balance(alice.pubkey()).await.unwrap();
+
+    let instruction = settle_instruction(&setup, bob.pubkey(), alice.pubkey());
+    process(&mut setup.context, instruction, &[]).await.unwrap();
+
+    let after = setup.context.banks_client.get_balance(alice.pubkey()).await.unwrap();
+    assert_eq!(after - before, rent, "First bidder should get the bid vault rent back");
+
+    assert_eq!(balance(&mut setup.context, &bob.pubkey(), &setup.mint_a).await, 200, "Winner should receive mint A");
+}
+
+// Trying to settle an auction that is still running.
+
+#[tokio::test]
+async fn settle_error_escrow_not_expired() {
+    let mut setup = setup().await;
+    let alice = setup.alice.insecure_clone();
+
+    place_bid(&mut setup, &alice, None, 500).await.unwrap();
+
+    let instruction = settle_instruction(&setup, alice.pubkey(), alice.pubkey());
+    let result = process(&mut setup.context, instruction, &[]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowNotExpired);
+}
+
+// An English auction can't be taken directly, and the maker can't pull mint A
+// out once a bid is locked.
+
+#[tokio::test]
+async fn take_and_refund_error_on_auction() {
+    let mut setup = setup().await;
+    let alice = setup.alice.insecure_clone();
+    let maker = setup.maker.insecure_clone();
+
+    let instruction = take(
+        anchor_escrow::id(),
+        spl_token::id(),
+        spl_token::id(),
+        alice.pubkey(),
+        maker.pubkey(),
+        setup.mint_a,
+        setup.mint_b,
+        setup.escrow,
+        setup.fee_recipient,
+        None,
+        None,
+        None,
+        0,
+        u64::MAX,
+        vec![],
+    );
+    let result = process(&mut setup.context, instruction, &[&alice]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::BiddingOnly);
+
+    place_bid(&mut setup, &alice, None, 500).await.unwrap();
+
+    let instruction = refund(anchor_escrow::id(), spl_token::id(), maker.pubkey(), setup.mint_a, setup.escrow);
+    let result = process(&mut setup.context, instruction, &[&maker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::AuctionHasBids);
+}
@@ -0,0 +1,123 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, Expiry, ExpiryMode, TimeSpec}}, anchor_lang::prelude::Clock, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Testing the expiry modes of an Escrow: a Unix timestamp, a slot height, or none.
+
+// Setting up an Escrow of 100 tokens of mint A for 100 tokens of mint B, with the
+// expiry built from the current clock.
+
+async fn setup(expiry: impl FnOnce(&Clock) -> Expiry) -> EscrowSetup {
+    EscrowSetup::start(program_test(200_000), 100, 100, |escrow, clock| Escrow {
+        expiry: expiry(clock),
+        ..escrow
+    }).await
+}
+
+// Taking an Escrow expiring at a slot height that has not been reached yet.
+
+#[tokio::test]
+async fn successful_take_before_slot_expiry() {
+    let mut setup = setup(|clock| Expiry::Slot(clock.slot + 1_000)).await;
+
+    let instruction = setup.take_instruction(None, u64::MAX);
+    process(&mut setup.context, instruction, &[&setup.taker]).await.unwrap();
+
+    let escrow_account = setup.context.banks_client.get_account(setup.escrow).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed");
+}
+
+// Trying to take an Escrow past its slot height, whatever the wall clock says.
+
+#[tokio::test]
+async fn take_error_escrow_expired_by_slot() {
+    let mut setup = setup(|clock| Expiry::Slot(clock.slot + 10)).await;
+
+    // Move the clock 20 slots ahead
+    let mut clock = setup.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.slot += 20;
+    setup.context.set_sysvar(&clock);
+
+    let instruction = setup.take_instruction(None, u64::MAX);
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowExpired);
+}
+
+// An Escrow without expiry stays takeable and can't be closed by a cranker.
+
+#[tokio::test]
+async fn close_expired_error_never_expires() {
+    let mut setup = setup(|_| Expiry::Never).await;
+
+    advance_clock(&mut setup.context, 10 * 365 * 24 * 3_600).await;
+
+    let instruction = close_expired(
+        anchor_escrow::id(),
+        spl_token::id(),
+        setup.taker.pubkey(),
+        setup.maker.pubkey(),
+        setup.mint_a,
+        setup.escrow,
+    );
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowNotExpired);
+
+    let instruction = setup.take_instruction(None, u64::MAX);
+    process(&mut setup.context, instruction, &[&setup.taker]).await.unwrap();
+}
+
+// Trying to make an Escrow whose relative expiry overflows the clock.
+
+#[tokio::test]
+async fn make_error_expiry_overflow() {
+    let mut test = program_test(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_mint(&mut test, mint_b, 100_000);
+    add_token_account(&mut test, mint_a, maker.pubkey(), 100_000);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            rand::thread_rng().gen(),
+            100,
+            100,
+            false,
+            ExpiryMode::Timestamp(TimeSpec::After(u64::MAX)),
+            None,
+            None,
+            0,
+            Default::default(),
+            None,
+            None,
+            None,
+            None,
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::MathOverflow);
+}
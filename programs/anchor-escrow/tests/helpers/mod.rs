@@ -1,11 +1,18 @@
 pub mod spl_token_helpers;
+pub mod spl_token_2022_helpers;
 
 use {
-    anchor_lang::error::ERROR_CODE_OFFSET,
-    solana_program_test::{BanksClient, BanksClientError, ProgramTestContext},
+    anchor_lang::{error::ERROR_CODE_OFFSET, AccountSerialize, Space},
+    rand::Rng,
+    anchor_spl::metadata::mpl_token_metadata::{self, accounts::Metadata, types::{Collection, Key}},
+    mpl_borsh::BorshSerialize,
+    solana_program_test::{BanksClient, BanksClientError, ProgramTest, ProgramTestContext},
     solana_sdk::{
+        account::Account as SolanaAccount,
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
         clock::Clock,
-        instruction::{Instruction, InstructionError},
+        instruction::{AccountMeta, Instruction, InstructionError},
+        native_token::LAMPORTS_PER_SOL,
         pubkey::Pubkey,
         signature::Keypair,
         signer::Signer,
@@ -14,9 +21,310 @@ use {
         transaction::{Transaction, TransactionError},
     },
     spl_associated_token_account::get_associated_token_address_with_program_id,
-    anchor_escrow::errors::EscrowErrors,
+    spl_token_helpers::{add_mint, add_token_account},
+    anchor_escrow::{errors::EscrowErrors, state::{BundleLeg, Config, DutchAuction, EnglishTerms, Escrow, Expiry, ExpiryMode, NftTerms, OracleTerms, SignedOrder, TimeSpec}},
 };
 
+/// Signer of the self-invocations through which the program emits its events.
+#[allow(dead_code)]
+pub fn event_authority(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], program_id).0
+}
+
+/// Associated token account of `owner`, or `None` when `mint` is settled in native SOL.
+#[allow(dead_code)]
+pub fn token_account(owner: &Pubkey, mint: &Pubkey, token_program_id: &Pubkey) -> Option<Pubkey> {
+    (*mint != spl_token::native_mint::id())
+        .then(|| get_associated_token_address_with_program_id(owner, mint, token_program_id))
+}
+
+/// Program test of the escrow program with a compute unit limit.
+#[allow(dead_code)]
+pub fn program_test(compute_max_units: u64) -> ProgramTest {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(compute_max_units);
+    test
+}
+
+/// Escrow of `amount` tokens of mint A for `receive` tokens of mint B under a random
+/// seed, open to any taker and never expiring. Tests turn on what they exercise
+/// with struct update syntax.
+#[allow(dead_code)]
+pub fn default_escrow(maker: Pubkey, mint_a: Pubkey, mint_b: Pubkey, amount: u64, receive: u64) -> Escrow {
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    Escrow {
+        seed,
+        maker,
+        mint_a,
+        mint_b,
+        amount,
+        receive,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    }
+}
+
+#[allow(dead_code)]
+pub fn escrow_address(escrow: &Escrow) -> Pubkey {
+    Pubkey::create_program_address(
+        &[b"escrow", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref(), &[escrow.bump]],
+        &anchor_escrow::id(),
+    ).unwrap()
+}
+
+fn escrow_account(escrow: &Escrow) -> SolanaAccount {
+    // Leave room for the fields the Escrow may grow, such as a top bidder
+    let mut escrow_data = vec![0u8; 8 + Escrow::INIT_SPACE];
+    escrow.try_serialize(&mut escrow_data.as_mut_slice()).unwrap();
+
+    SolanaAccount {
+        lamports: u32::MAX as u64,
+        data: escrow_data,
+        owner: anchor_escrow::id(),
+        ..SolanaAccount::default()
+    }
+}
+
+#[allow(dead_code)]
+pub fn add_escrow(test: &mut ProgramTest, escrow: &Escrow) -> Pubkey {
+    let escrow_pubkey = escrow_address(escrow);
+    test.add_account(escrow_pubkey, escrow_account(escrow));
+    escrow_pubkey
+}
+
+/// Writes an Escrow into a running Bank, as `add_escrow` does before it starts.
+#[allow(dead_code)]
+pub fn set_escrow(context: &mut ProgramTestContext, escrow: &Escrow) -> Pubkey {
+    let escrow_pubkey = escrow_address(escrow);
+    context.set_account(&escrow_pubkey, &escrow_account(escrow).into());
+    escrow_pubkey
+}
+
+/// Escrow of SPL Token mints written directly into a running Bank, with a taker
+/// holding 100_000 tokens of mint B and a Config without fees.
+#[allow(dead_code)]
+pub struct EscrowSetup {
+    pub context: ProgramTestContext,
+    pub maker: Keypair,
+    pub taker: Keypair,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub escrow: Pubkey,
+    pub fee_recipient: Pubkey,
+    /// Unix timestamp at which the Escrow was written.
+    pub now: i64,
+}
+
+impl EscrowSetup {
+    /// Starts `test` with a vault of `amount` tokens of mint A, then writes the Escrow
+    /// `escrow` makes of the default Escrow of `amount` for `receive` and the clock.
+    #[allow(dead_code)]
+    pub async fn start(
+        mut test: ProgramTest,
+        amount: u64,
+        receive: u64,
+        escrow: impl FnOnce(Escrow, &Clock) -> Escrow,
+    ) -> Self {
+        let maker = Keypair::new();
+        let taker = Keypair::new();
+        let mint_a = Keypair::new().pubkey();
+        let mint_b = Keypair::new().pubkey();
+        let default = default_escrow(maker.pubkey(), mint_a, mint_b, amount, receive);
+
+        add_mint(&mut test, mint_a, 100_000);
+        add_mint(&mut test, mint_b, 100_000);
+        add_token_account(&mut test, mint_b, taker.pubkey(), 100_000);
+        add_token_account(&mut test, mint_a, escrow_address(&default), amount);
+
+        // Setup config account without fees
+        let fee_recipient = Keypair::new().pubkey();
+        add_config(&mut test, fee_recipient, 0, 0);
+
+        let mut context = test.start_with_context().await;
+
+        // Airdrop SOL to maker and taker
+        for wallet in [&maker, &taker] {
+            let _ = airdrop(&mut context.banks_client, &context.payer, &wallet.pubkey(), LAMPORTS_PER_SOL).await;
+        }
+
+        let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+        let escrow = set_escrow(&mut context, &escrow(default, &clock));
+
+        Self { context, maker, taker, mint_a, mint_b, escrow, fee_recipient, now: clock.unix_timestamp }
+    }
+
+    /// Takes the whole Escrow for at most `max_amount_b`, reading `price_feed` if the
+    /// Escrow is pegged to an oracle.
+    #[allow(dead_code)]
+    pub fn take_instruction(&self, price_feed: Option<Pubkey>, max_amount_b: u64) -> Instruction {
+        take(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            self.taker.pubkey(),
+            self.maker.pubkey(),
+            self.mint_a,
+            self.mint_b,
+            self.escrow,
+            self.fee_recipient,
+            None,
+            None,
+            price_feed,
+            0,
+            max_amount_b,
+            vec![],
+        )
+    }
+}
+
+/// Signs `instruction` with the payer and `signers`, then processes it.
+#[allow(dead_code)]
+pub async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    process_instructions(context, &[instruction], signers).await
+}
+
+/// Processes `instructions` in a single transaction, signed as `process` signs one.
+#[allow(dead_code)]
+pub async fn process_instructions(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&context.payer.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&[&context.payer], signers].concat(), blockhash);
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// Balance of the SPL Token associated token account of `owner` for `mint`.
+#[allow(dead_code)]
+pub async fn balance(context: &mut ProgramTestContext, owner: &Pubkey, mint: &Pubkey) -> u64 {
+    let ata = token_account(owner, mint, &spl_token::id()).unwrap();
+    spl_token_helpers::get_token_balance(&mut context.banks_client, ata).await.unwrap()
+}
+
+fn config_account(fee_recipient: Pubkey, fee_bps: u16, referral_bps: u16) -> (Pubkey, SolanaAccount) {
+    let (config_pubkey, bump) = Pubkey::find_program_address(&[b"config"], &anchor_escrow::id());
+
+    let mut config_data = vec![0u8; 8 + Config::INIT_SPACE];
+    Config {
+        authority: Pubkey::new_unique(),
+        fee_recipient,
+        fee_bps,
+        referral_bps,
+        bump,
+    }.try_serialize(&mut config_data.as_mut_slice()).unwrap();
+
+    let account = SolanaAccount {
+        lamports: u32::MAX as u64,
+        data: config_data,
+        owner: anchor_escrow::id(),
+        ..SolanaAccount::default()
+    };
+
+    (config_pubkey, account)
+}
+
+#[allow(dead_code)]
+pub fn add_config(test: &mut ProgramTest, fee_recipient: Pubkey, fee_bps: u16, referral_bps: u16) -> Pubkey {
+    let (config_pubkey, account) = config_account(fee_recipient, fee_bps, referral_bps);
+    test.add_account(config_pubkey, account);
+    config_pubkey
+}
+
+/// Writes the Config into a running Bank, as `add_config` does before it starts.
+#[allow(dead_code)]
+pub fn set_config(context: &mut ProgramTestContext, fee_recipient: Pubkey, fee_bps: u16, referral_bps: u16) -> Pubkey {
+    let (config_pubkey, account) = config_account(fee_recipient, fee_bps, referral_bps);
+    context.set_account(&config_pubkey, &account.into());
+    config_pubkey
+}
+
+/// Adds the ProgramData account of the escrow program, as the upgradeable loader
+/// would create it on deploy, with `upgrade_authority` as its upgrade authority.
+#[allow(dead_code)]
+pub fn add_program_data(test: &mut ProgramTest, upgrade_authority: Pubkey) -> Pubkey {
+    let (program_data_pubkey, _) = Pubkey::find_program_address(
+        &[anchor_escrow::id().as_ref()],
+        &bpf_loader_upgradeable::id(),
+    );
+
+    let program_data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(upgrade_authority),
+    }).unwrap();
+
+    test.add_account(
+        program_data_pubkey,
+        SolanaAccount {
+            lamports: u32::MAX as u64,
+            data: program_data,
+            owner: bpf_loader_upgradeable::id(),
+            ..SolanaAccount::default()
+        },
+    );
+
+    program_data_pubkey
+}
+
+/// Adds the Metaplex metadata account of `mint`, optionally member of `collection`
+/// with the given verification status.
+#[allow(dead_code)]
+pub fn add_metadata(test: &mut ProgramTest, mint: Pubkey, collection: Option<(Pubkey, bool)>) -> Pubkey {
+    let (metadata_pubkey, _) = Metadata::find_pda(&mint);
+
+    let metadata = Metadata {
+        key: Key::MetadataV1,
+        update_authority: Pubkey::new_unique(),
+        mint,
+        name: "Escrow NFT".to_string(),
+        symbol: "ENFT".to_string(),
+        uri: String::new(),
+        seller_fee_basis_points: 0,
+        creators: None,
+        primary_sale_happened: false,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: None,
+        collection: collection.map(|(key, verified)| Collection { verified, key }),
+        uses: None,
+        collection_details: None,
+        programmable_config: None,
+    };
+
+    test.add_account(
+        metadata_pubkey,
+        SolanaAccount {
+            lamports: u32::MAX as u64,
+            data: metadata.try_to_vec().unwrap(),
+            owner: mpl_token_metadata::ID,
+            ..SolanaAccount::default()
+        },
+    );
+
+    metadata_pubkey
+}
+
 #[allow(dead_code)]
 pub async fn airdrop(
     banks_client: &mut BanksClient,
@@ -44,6 +352,14 @@ pub async fn advance_clock(
     context.set_sysvar(&clock);
 }
 
+/// Whether the program invoked itself from a top-level instruction, as
+/// `emit_cpi!` does for every event.
+#[allow(dead_code)]
+pub fn emits_event_cpi(log_messages: &[String]) -> bool {
+    let invoke = format!("Program {} invoke [2]", anchor_escrow::id());
+    log_messages.contains(&invoke)
+}
+
 #[allow(dead_code)]
 pub fn assert_escrow_error(
     error: BanksClientError, 
@@ -62,21 +378,31 @@ pub fn assert_escrow_error(
     }
 }
 
-#[allow(dead_code)]
+#[allow(dead_code, clippy::too_many_arguments)]
 pub fn make(
     program_id: Pubkey,
-    token_program_id: Pubkey,
+    token_program_a: Pubkey,
+    token_program_b: Pubkey,
     seed: u64,
     deposit: u64,
     receive: u64,
-    expiry: u64,
+    net_receive: bool,
+    expiry: ExpiryMode,
+    taker: Option<Pubkey>,
+    allowlist: Option<[u8; 32]>,
+    allowed_mint_flags: u8,
+    nft: NftTerms,
+    auction: Option<DutchAuction>,
+    english: Option<EnglishTerms>,
+    oracle: Option<OracleTerms>,
+    start_at: Option<TimeSpec>,
     maker: Pubkey,
     mint_a: Pubkey,
     mint_b: Pubkey,
 ) -> Instruction {
-    let maker_ata_a = get_associated_token_address_with_program_id(&maker, &mint_a, &token_program_id);
+    let maker_ata_a = token_account(&maker, &mint_a, &token_program_a);
     let (escrow, _) = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), seed.to_le_bytes().as_ref()], &program_id);
-    let vault = get_associated_token_address_with_program_id(&escrow, &mint_a, &token_program_id);
+    let vault = token_account(&escrow, &mint_a, &token_program_a);
 
     Instruction {
         program_id,
@@ -89,8 +415,11 @@ pub fn make(
                 escrow,
                 vault,
                 associated_token_program: spl_associated_token_account::id(),
-                token_program: token_program_id,
+                token_program_a,
+                token_program_b,
                 system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
             },
             None,
         ),
@@ -99,26 +428,46 @@ pub fn make(
                 seed,
                 deposit,
                 receive,
+                net_receive,
                 expiry,
+                taker,
+                allowlist,
+                allowed_mint_flags,
+                nft,
+                auction,
+                english,
+                oracle,
+                start_at,
             },
         )
     }
 }
 
-#[allow(dead_code)]
+#[allow(dead_code, clippy::too_many_arguments)]
 pub fn take(
     program_id: Pubkey,
-    token_program_id: Pubkey,
+    token_program_a: Pubkey,
+    token_program_b: Pubkey,
     taker: Pubkey,
     maker: Pubkey,
     mint_a: Pubkey,
     mint_b: Pubkey,
     escrow: Pubkey,
+    fee_recipient: Pubkey,
+    referrer: Option<Pubkey>,
+    metadata_b: Option<Pubkey>,
+    price_feed: Option<Pubkey>,
+    min_amount_a: u64,
+    max_amount_b: u64,
+    proof: Vec<[u8; 32]>,
 ) -> Instruction {
-    let taker_ata_a = get_associated_token_address_with_program_id(&taker, &mint_a, &token_program_id);
-    let taker_ata_b = get_associated_token_address_with_program_id(&taker, &mint_b, &token_program_id);
-    let maker_ata_b = get_associated_token_address_with_program_id(&maker, &mint_b, &token_program_id);
-    let vault = get_associated_token_address_with_program_id(&escrow, &mint_a, &token_program_id);
+    let taker_ata_a = token_account(&taker, &mint_a, &token_program_a);
+    let taker_ata_b = token_account(&taker, &mint_b, &token_program_b);
+    let maker_ata_b = token_account(&maker, &mint_b, &token_program_b);
+    let vault = token_account(&escrow, &mint_a, &token_program_a);
+    let (config, _) = Pubkey::find_program_address(&[b"config"], &program_id);
+    let fee_recipient_ata_b = token_account(&fee_recipient, &mint_b, &token_program_b);
+    let referrer_ata_b = referrer.and_then(|referrer| token_account(&referrer, &mint_b, &token_program_b));
 
     Instruction {
         program_id,
@@ -131,15 +480,248 @@ pub fn take(
                 taker_ata_a,
                 taker_ata_b,
                 maker_ata_b,
+                config,
+                fee_recipient,
+                fee_recipient_ata_b,
+                referrer,
+                referrer_ata_b,
+                escrow,
+                vault,
+                metadata_b,
+                price_feed,
+                associated_token_program: spl_associated_token_account::id(),
+                token_program_a,
+                token_program_b,
+                system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::Take { min_amount_a, max_amount_b, proof }),
+    }
+}
+
+/// `escrows` lists the `(escrow, maker)` pairs to fill, all trading `mint_a` for `mint_b`.
+#[allow(dead_code, clippy::too_many_arguments)]
+pub fn take_many(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    taker: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    fee_recipient: Pubkey,
+    escrows: &[(Pubkey, Pubkey)],
+    min_amount_a: u64,
+    max_amount_b: u64,
+) -> Instruction {
+    let ata = |owner: &Pubkey, mint: &Pubkey| get_associated_token_address_with_program_id(owner, mint, &token_program_id);
+    let (config, _) = Pubkey::find_program_address(&[b"config"], &program_id);
+
+    let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+        &anchor_escrow::accounts::TakeMany {
+            taker,
+            mint_a,
+            mint_b,
+            taker_ata_a: ata(&taker, &mint_a),
+            taker_ata_b: ata(&taker, &mint_b),
+            config,
+            fee_recipient,
+            fee_recipient_ata_b: ata(&fee_recipient, &mint_b),
+            associated_token_program: spl_associated_token_account::id(),
+            token_program_a: token_program_id,
+            token_program_b: token_program_id,
+            system_program: system_program::id(),
+            event_authority: event_authority(&program_id),
+            program: program_id,
+        },
+        None,
+    );
+    for (escrow, maker) in escrows {
+        accounts.extend([
+            AccountMeta::new(*escrow, false),
+            AccountMeta::new(ata(escrow, &mint_a), false),
+            AccountMeta::new(*maker, false),
+            AccountMeta::new(ata(maker, &mint_b), false),
+        ]);
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::TakeMany { min_amount_a, max_amount_b }),
+    }
+}
+
+#[allow(dead_code, clippy::too_many_arguments)]
+pub fn take_partial(
+    program_id: Pubkey,
+    token_program_a: Pubkey,
+    token_program_b: Pubkey,
+    taker: Pubkey,
+    maker: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    escrow: Pubkey,
+    fee_recipient: Pubkey,
+    referrer: Option<Pubkey>,
+    metadata_b: Option<Pubkey>,
+    price_feed: Option<Pubkey>,
+    amount_b: u64,
+    min_amount_a: u64,
+    max_amount_b: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let mut instruction = take(program_id, token_program_a, token_program_b, taker, maker, mint_a, mint_b, escrow, fee_recipient, referrer, metadata_b, price_feed, 0, u64::MAX, vec![]);
+    instruction.data = anchor_lang::InstructionData::data(&anchor_escrow::instruction::TakePartial { amount_b, min_amount_a, max_amount_b, proof });
+    instruction
+}
+
+#[allow(dead_code)]
+pub fn init_config(
+    program_id: Pubkey,
+    authority: Pubkey,
+    fee_recipient: Pubkey,
+    fee_bps: u16,
+    referral_bps: u16,
+) -> Instruction {
+    let (config, _) = Pubkey::find_program_address(&[b"config"], &program_id);
+    let (program_data, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+
+    Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::InitConfig {
+                authority,
+                fee_recipient,
+                program_data,
+                config,
+                system_program: system_program::id(),
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::InitConfig { fee_bps, referral_bps }),
+    }
+}
+
+#[allow(dead_code)]
+pub fn update_config(
+    program_id: Pubkey,
+    authority: Pubkey,
+    fee_recipient: Pubkey,
+    fee_bps: u16,
+    referral_bps: u16,
+) -> Instruction {
+    let (config, _) = Pubkey::find_program_address(&[b"config"], &program_id);
+
+    Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::UpdateConfig {
+                authority,
+                fee_recipient,
+                config,
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::UpdateConfig { fee_bps, referral_bps }),
+    }
+}
+
+#[allow(dead_code)]
+pub fn update(
+    program_id: Pubkey,
+    maker: Pubkey,
+    escrow: Pubkey,
+    receive: Option<u64>,
+    expiry: Option<ExpiryMode>,
+    expected_receive: Option<u64>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::Update {
+                maker,
+                escrow,
+                event_authority: event_authority(&program_id),
+                program: program_id,
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(
+            &anchor_escrow::instruction::Update {
+                receive,
+                expiry,
+                expected_receive,
+            },
+        ),
+    }
+}
+
+#[allow(dead_code)]
+pub fn deposit_more(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    maker: Pubkey,
+    mint_a: Pubkey,
+    escrow: Pubkey,
+    amount: u64,
+    scale_receive: bool,
+) -> Instruction {
+    let maker_ata_a = token_account(&maker, &mint_a, &token_program_id);
+    let vault = token_account(&escrow, &mint_a, &token_program_id);
+
+    Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::DepositMore {
+                maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                vault,
+                token_program_a: token_program_id,
+                system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::DepositMore { amount, scale_receive }),
+    }
+}
+
+#[allow(dead_code)]
+pub fn withdraw_some(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    maker: Pubkey,
+    mint_a: Pubkey,
+    escrow: Pubkey,
+    amount: u64,
+    scale_receive: bool,
+) -> Instruction {
+    let maker_ata_a = token_account(&maker, &mint_a, &token_program_id);
+    let vault = token_account(&escrow, &mint_a, &token_program_id);
+
+    Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::WithdrawSome {
+                maker,
+                mint_a,
+                maker_ata_a,
                 escrow,
                 vault,
                 associated_token_program: spl_associated_token_account::id(),
-                token_program: token_program_id,
+                token_program_a: token_program_id,
                 system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
             },
             None,
         ),
-        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::Take {}),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::WithdrawSome { amount, scale_receive }),
     }
 }
 
@@ -151,8 +733,8 @@ pub fn refund(
     mint_a: Pubkey,
     escrow: Pubkey,
 ) -> Instruction {
-    let maker_ata_a = get_associated_token_address_with_program_id(&maker, &mint_a, &token_program_id);
-    let vault = get_associated_token_address_with_program_id(&escrow, &mint_a, &token_program_id);
+    let maker_ata_a = token_account(&maker, &mint_a, &token_program_id);
+    let vault = token_account(&escrow, &mint_a, &token_program_id);
 
     Instruction {
         program_id,
@@ -164,11 +746,322 @@ pub fn refund(
                 escrow,
                 vault,
                 associated_token_program: spl_associated_token_account::id(),
-                token_program: token_program_id,
+                token_program_a: token_program_id,
                 system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
             },
             None,
         ),
         data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::Refund {}),
     }
-}
\ No newline at end of file
+}
+#[allow(dead_code)]
+pub fn close_expired(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    cranker: Pubkey,
+    maker: Pubkey,
+    mint_a: Pubkey,
+    escrow: Pubkey,
+) -> Instruction {
+    let maker_ata_a = token_account(&maker, &mint_a, &token_program_id);
+    let vault = token_account(&escrow, &mint_a, &token_program_id);
+
+    Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::CloseExpired {
+                cranker,
+                maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::id(),
+                token_program_a: token_program_id,
+                system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::CloseExpired {}),
+    }
+}
+
+#[allow(dead_code, clippy::too_many_arguments)]
+pub fn bid(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    bidder: Pubkey,
+    maker: Pubkey,
+    mint_b: Pubkey,
+    escrow: Pubkey,
+    previous_bidder: Option<Pubkey>,
+    amount: u64,
+) -> Instruction {
+    let bidder_ata_b = get_associated_token_address_with_program_id(&bidder, &mint_b, &token_program_id);
+    let previous_bidder_ata_b = previous_bidder
+        .map(|previous_bidder| get_associated_token_address_with_program_id(&previous_bidder, &mint_b, &token_program_id));
+    let bid_vault = get_associated_token_address_with_program_id(&escrow, &mint_b, &token_program_id);
+
+    Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::Bid {
+                bidder,
+                maker,
+                mint_b,
+                bidder_ata_b,
+                previous_bidder,
+                previous_bidder_ata_b,
+                escrow,
+                bid_vault,
+                associated_token_program: spl_associated_token_account::id(),
+                token_program_b: token_program_id,
+                system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::Bid { amount, proof: vec![] }),
+    }
+}
+
+#[allow(dead_code, clippy::too_many_arguments)]
+pub fn settle_auction(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    payer: Pubkey,
+    maker: Pubkey,
+    winner: Pubkey,
+    first_bidder: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    escrow: Pubkey,
+    fee_recipient: Pubkey,
+) -> Instruction {
+    let ata = |owner: &Pubkey, mint: &Pubkey| get_associated_token_address_with_program_id(owner, mint, &token_program_id);
+    let (config, _) = Pubkey::find_program_address(&[b"config"], &program_id);
+
+    Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::SettleAuction {
+                payer,
+                maker,
+                winner,
+                first_bidder,
+                mint_a,
+                mint_b,
+                winner_ata_a: ata(&winner, &mint_a),
+                maker_ata_b: ata(&maker, &mint_b),
+                config,
+                fee_recipient,
+                fee_recipient_ata_b: ata(&fee_recipient, &mint_b),
+                escrow,
+                vault: ata(&escrow, &mint_a),
+                bid_vault: ata(&escrow, &mint_b),
+                associated_token_program: spl_associated_token_account::id(),
+                token_program_a: token_program_id,
+                token_program_b: token_program_id,
+                system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::SettleAuction {}),
+    }
+}
+
+/// The Ed25519 program instruction checking `signature` of the order, followed by
+/// `fill_signed_order`.
+#[allow(dead_code)]
+pub fn fill_signed_order(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    taker: Pubkey,
+    order: SignedOrder,
+    signature: &[u8; 64],
+    fee_recipient: Pubkey,
+) -> [Instruction; 2] {
+    let ata = |owner: &Pubkey, mint: &Pubkey| get_associated_token_address_with_program_id(owner, mint, &token_program_id);
+    let (config, _) = Pubkey::find_program_address(&[b"config"], &program_id);
+    let (order_nonce, _) = Pubkey::find_program_address(&[b"nonce", order.maker.as_ref()], &program_id);
+    let (delegate, _) = Pubkey::find_program_address(&[b"delegate"], &program_id);
+
+    let verify = Instruction {
+        program_id: solana_sdk::ed25519_program::id(),
+        accounts: vec![],
+        data: anchor_escrow::ed25519::instruction_data(&order.maker, signature, &order.message()),
+    };
+
+    let fill = Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::FillSignedOrder {
+                taker,
+                maker: order.maker,
+                mint_a: order.mint_a,
+                mint_b: order.mint_b,
+                maker_ata_a: ata(&order.maker, &order.mint_a),
+                taker_ata_a: ata(&taker, &order.mint_a),
+                taker_ata_b: ata(&taker, &order.mint_b),
+                maker_ata_b: ata(&order.maker, &order.mint_b),
+                config,
+                fee_recipient,
+                fee_recipient_ata_b: ata(&fee_recipient, &order.mint_b),
+                order_nonce,
+                delegate,
+                instructions: solana_sdk::sysvar::instructions::id(),
+                associated_token_program: spl_associated_token_account::id(),
+                token_program_a: token_program_id,
+                token_program_b: token_program_id,
+                system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::FillSignedOrder { order }),
+    };
+
+    [verify, fill]
+}
+
+#[allow(dead_code)]
+pub fn cancel_orders_up_to(program_id: Pubkey, maker: Pubkey, nonce: u64) -> Instruction {
+    let (order_nonce, _) = Pubkey::find_program_address(&[b"nonce", maker.as_ref()], &program_id);
+
+    Instruction {
+        program_id,
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &anchor_escrow::accounts::CancelOrders {
+                maker,
+                order_nonce,
+                system_program: system_program::id(),
+                event_authority: event_authority(&program_id),
+                program: program_id,
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::CancelOrdersUpTo { nonce }),
+    }
+}
+
+/// Bundle leg accounts: `[mint, source, destination, token program]` for each leg.
+#[allow(dead_code)]
+pub fn bundle_legs(legs: &[BundleLeg], token_program_id: Pubkey, source_owner: Pubkey, destination_owner: Pubkey) -> Vec<AccountMeta> {
+    legs.iter()
+        .flat_map(|leg| [
+            AccountMeta::new(leg.mint, false),
+            AccountMeta::new(get_associated_token_address_with_program_id(&source_owner, &leg.mint, &token_program_id), false),
+            AccountMeta::new(get_associated_token_address_with_program_id(&destination_owner, &leg.mint, &token_program_id), false),
+            AccountMeta::new_readonly(token_program_id, false),
+        ])
+        .collect()
+}
+
+#[allow(dead_code, clippy::too_many_arguments)]
+pub fn make_bundle(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    maker: Pubkey,
+    seed: u64,
+    give: Vec<BundleLeg>,
+    receive: Vec<BundleLeg>,
+    expiry: ExpiryMode,
+    start_at: Option<TimeSpec>,
+) -> Instruction {
+    let (bundle, _) = Pubkey::find_program_address(&[b"bundle", maker.as_ref(), seed.to_le_bytes().as_ref()], &program_id);
+
+    let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+        &anchor_escrow::accounts::MakeBundle {
+            maker,
+            bundle,
+            associated_token_program: spl_associated_token_account::id(),
+            system_program: system_program::id(),
+            event_authority: event_authority(&program_id),
+            program: program_id,
+        },
+        None,
+    );
+    accounts.extend(bundle_legs(&give, token_program_id, maker, bundle));
+
+    Instruction {
+        program_id,
+        accounts,
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::MakeBundle { seed, give, receive, expiry, start_at }),
+    }
+}
+
+#[allow(dead_code, clippy::too_many_arguments)]
+pub fn take_bundle(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    taker: Pubkey,
+    maker: Pubkey,
+    bundle: Pubkey,
+    fee_recipient: Pubkey,
+    give: &[BundleLeg],
+    receive: &[BundleLeg],
+) -> Instruction {
+    let (config, _) = Pubkey::find_program_address(&[b"config"], &program_id);
+
+    let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+        &anchor_escrow::accounts::TakeBundle {
+            taker,
+            maker,
+            bundle,
+            config,
+            fee_recipient,
+            associated_token_program: spl_associated_token_account::id(),
+            system_program: system_program::id(),
+            event_authority: event_authority(&program_id),
+            program: program_id,
+        },
+        None,
+    );
+    accounts.extend(bundle_legs(give, token_program_id, bundle, taker));
+    accounts.extend(bundle_legs(receive, token_program_id, taker, maker));
+    accounts.extend(receive.iter().map(|leg| {
+        AccountMeta::new(get_associated_token_address_with_program_id(&fee_recipient, &leg.mint, &token_program_id), false)
+    }));
+
+    Instruction {
+        program_id,
+        accounts,
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::TakeBundle {}),
+    }
+}
+
+#[allow(dead_code)]
+pub fn refund_bundle(
+    program_id: Pubkey,
+    token_program_id: Pubkey,
+    maker: Pubkey,
+    bundle: Pubkey,
+    give: &[BundleLeg],
+) -> Instruction {
+    let mut accounts = anchor_lang::ToAccountMetas::to_account_metas(
+        &anchor_escrow::accounts::RefundBundle {
+            maker,
+            bundle,
+            associated_token_program: spl_associated_token_account::id(),
+            system_program: system_program::id(),
+            event_authority: event_authority(&program_id),
+            program: program_id,
+        },
+        None,
+    );
+    accounts.extend(bundle_legs(give, token_program_id, bundle, maker));
+
+    Instruction {
+        program_id,
+        accounts,
+        data: anchor_lang::InstructionData::data(&anchor_escrow::instruction::RefundBundle {}),
+    }
+}
@@ -0,0 +1,202 @@
+use {
+    solana_program_test::BanksClient,
+    solana_sdk::{
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        system_instruction::create_account,
+        transaction::Transaction,
+        transport::TransportError,
+    },
+    spl_token_2022::{
+        extension::{
+            transfer_fee::instruction::initialize_transfer_fee_config,
+            transfer_hook::instruction::initialize as initialize_transfer_hook,
+            ExtensionType,
+            StateWithExtensions,
+        },
+        state::{Account as TokenAccount, Mint},
+    },
+};
+
+#[allow(dead_code)]
+pub async fn create_mint_with_transfer_fee(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+) -> Result<Pubkey, TransportError> {
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+    let lamports = banks_client.get_rent().await?.minimum_balance(space);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                lamports,
+                space as u64,
+                &spl_token_2022::id(),
+            ),
+            initialize_transfer_fee_config(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                None,
+                None,
+                transfer_fee_basis_points,
+                maximum_fee,
+            ).unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                &payer.pubkey(),
+                None,
+                6,
+            ).unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[payer, &mint],
+        banks_client.get_latest_blockhash().await?,
+    );
+
+    banks_client.process_transaction(transaction).await?;
+
+    Ok(mint.pubkey())
+}
+
+#[allow(dead_code)]
+pub async fn create_mint_with_transfer_hook(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    mint: &Keypair,
+    hook_program: Pubkey,
+) -> Result<Pubkey, TransportError> {
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook]).unwrap();
+    let lamports = banks_client.get_rent().await?.minimum_balance(space);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                lamports,
+                space as u64,
+                &spl_token_2022::id(),
+            ),
+            initialize_transfer_hook(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                None,
+                Some(hook_program),
+            ).unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                &payer.pubkey(),
+                None,
+                6,
+            ).unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[payer, mint],
+        banks_client.get_latest_blockhash().await?,
+    );
+
+    banks_client.process_transaction(transaction).await?;
+
+    Ok(mint.pubkey())
+}
+
+#[allow(dead_code)]
+pub async fn create_mint_with_extensions(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    extensions: &[ExtensionType],
+    initialize_extensions: impl FnOnce(&Pubkey) -> Vec<Instruction>,
+    freeze_authority: Option<&Pubkey>,
+) -> Result<Pubkey, TransportError> {
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+    let lamports = banks_client.get_rent().await?.minimum_balance(space);
+
+    let mut instructions = vec![create_account(
+        &payer.pubkey(),
+        &mint.pubkey(),
+        lamports,
+        space as u64,
+        &spl_token_2022::id(),
+    )];
+    instructions.extend(initialize_extensions(&mint.pubkey()));
+    instructions.push(spl_token_2022::instruction::initialize_mint2(
+        &spl_token_2022::id(),
+        &mint.pubkey(),
+        &payer.pubkey(),
+        freeze_authority,
+        6,
+    ).unwrap());
+
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &mint],
+        banks_client.get_latest_blockhash().await?,
+    );
+
+    banks_client.process_transaction(transaction).await?;
+
+    Ok(mint.pubkey())
+}
+
+#[allow(dead_code)]
+pub async fn create_and_mint_to_token_2022_account(
+    banks_client: &mut BanksClient,
+    mint_pubkey: Pubkey,
+    payer: &Keypair,
+    owner: Pubkey,
+    amount: u64,
+) -> Result<Pubkey, TransportError> {
+    let account_pubkey = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &owner,
+        &mint_pubkey,
+        &spl_token_2022::id(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer.pubkey(),
+                &owner,
+                &mint_pubkey,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::mint_to(
+                &spl_token_2022::id(),
+                &mint_pubkey,
+                &account_pubkey,
+                &payer.pubkey(),
+                &[],
+                amount,
+            ).unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[payer],
+        banks_client.get_latest_blockhash().await?,
+    );
+
+    banks_client.process_transaction(transaction).await?;
+
+    Ok(account_pubkey)
+}
+
+#[allow(dead_code)]
+pub async fn get_token_2022_balance(banks_client: &mut BanksClient, pubkey: Pubkey) -> Result<u64, TransportError> {
+    let account = banks_client
+        .get_account(pubkey)
+        .await?
+        .ok_or_else(|| TransportError::Custom("Token account not found".to_string()))?;
+    StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .map(|account| account.base.amount)
+        .map_err(|err| TransportError::Custom(format!("Failed to unpack Token account: {:?}", err)))
+}
@@ -1,6 +1,8 @@
 use {
-    solana_program_test::{BanksClient, BanksClientError},
+    solana_program_test::{BanksClient, BanksClientError, ProgramTest, ProgramTestContext},
     solana_sdk::{
+        account::Account as SolanaAccount,
+        program_option::COption,
         program_pack::Pack,
         pubkey::Pubkey,
         signature::Keypair,
@@ -9,9 +11,85 @@ use {
         transaction::Transaction,
         transport::TransportError,
     },
-    spl_token::state::{Account as TokenAccount, Mint},
+    spl_token::state::{Account as TokenAccount, AccountState, Mint},
 };
 
+#[allow(dead_code)]
+pub fn add_mint(test: &mut ProgramTest, mint: Pubkey, supply: u64) {
+    let mut mint_data = vec![0u8; Mint::LEN];
+    Mint {
+        is_initialized: true,
+        decimals: 6,
+        mint_authority: COption::None,
+        supply,
+        ..Mint::default()
+    }.pack_into_slice(&mut mint_data);
+    test.add_account(
+        mint,
+        SolanaAccount {
+            lamports: u32::MAX as u64,
+            data: mint_data,
+            owner: spl_token::id(),
+            ..SolanaAccount::default()
+        },
+    );
+}
+
+/// Adds an NFT mint: no decimals and a supply of one.
+#[allow(dead_code)]
+pub fn add_nft_mint(test: &mut ProgramTest, mint: Pubkey) {
+    let mut mint_data = vec![0u8; Mint::LEN];
+    Mint {
+        is_initialized: true,
+        decimals: 0,
+        mint_authority: COption::None,
+        supply: 1,
+        ..Mint::default()
+    }.pack_into_slice(&mut mint_data);
+    test.add_account(
+        mint,
+        SolanaAccount {
+            lamports: u32::MAX as u64,
+            data: mint_data,
+            owner: spl_token::id(),
+            ..SolanaAccount::default()
+        },
+    );
+}
+
+fn token_account_state(mint: Pubkey, owner: Pubkey, amount: u64) -> SolanaAccount {
+    let mut account_data = vec![0u8; TokenAccount::LEN];
+    TokenAccount {
+        mint,
+        owner,
+        amount,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    }.pack_into_slice(&mut account_data);
+    SolanaAccount {
+        lamports: u32::MAX as u64,
+        data: account_data,
+        owner: spl_token::id(),
+        ..SolanaAccount::default()
+    }
+}
+
+#[allow(dead_code)]
+pub fn add_token_account(test: &mut ProgramTest, mint: Pubkey, owner: Pubkey, amount: u64) -> Pubkey {
+    let account = spl_associated_token_account::get_associated_token_address(&owner, &mint);
+    test.add_account(account, token_account_state(mint, owner, amount));
+    account
+}
+
+/// Writes a token account into a running Bank, as `add_token_account` does before
+/// it starts.
+#[allow(dead_code)]
+pub fn set_token_account(context: &mut ProgramTestContext, mint: Pubkey, owner: Pubkey, amount: u64) -> Pubkey {
+    let account = spl_associated_token_account::get_associated_token_address(&owner, &mint);
+    context.set_account(&account, &token_account_state(mint, owner, amount).into());
+    account
+}
+
 #[allow(dead_code)]
 pub async fn create_and_mint_to_token_account(
     banks_client: &mut BanksClient,
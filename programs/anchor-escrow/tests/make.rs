@@ -1,7 +1,7 @@
 mod helpers;
 
 use {
-    anchor_escrow::state::Escrow, anchor_lang::AccountDeserialize, helpers::{spl_token_helpers::*, *}, rand::Rng, solana_program_test::*, solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction}, std::u64
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, ExpiryMode}}, anchor_lang::AccountDeserialize, helpers::{spl_token_helpers::*, *}, rand::Rng, solana_program_test::*, solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction}
 };
 
 // Testing the Make instruction using the spl_token_helpers and creating
@@ -38,10 +38,20 @@ async fn test_successful_make() {
         &[make(
             anchor_escrow::id(),
             spl_token::id(),
+            spl_token::id(),
             seed,
             100,
             100,
-            u64::MAX,
+            false,
+            ExpiryMode::Never,
+            None,
+            None,
+            0,
+            Default::default(),
+            None,
+            None,
+            None,
+            None,
             maker.pubkey(),
             mint_a,
             mint_b,
@@ -50,7 +60,10 @@ async fn test_successful_make() {
     );
 
     transaction.sign(&[&payer, &maker], recent_blockhash);
-    banks_client.process_transaction(transaction).await.unwrap();
+
+    let result = banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    assert!(result.result.is_ok());
+    assert!(emits_event_cpi(&result.metadata.unwrap().log_messages), "Make should emit its event through a CPI");
 
     // Verify escrow state
     let (escrow_pubkey, _) = Pubkey::find_program_address(
@@ -72,5 +85,139 @@ async fn test_successful_make() {
     assert_eq!(escrow_account.mint_a, mint_a);
     assert_eq!(escrow_account.mint_b, mint_b);
     assert_eq!(escrow_account.receive, 100);
+}
+// Testing the Make instruction with native SOL as mint A: the deposit is held
+// by the Escrow account itself and no vault is created.
+
+#[tokio::test]
+async fn test_successful_native_make() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let seed: u64 = rand::thread_rng().gen();
+    let mint_a = spl_token::native_mint::id();
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), 2 * LAMPORTS_PER_SOL).await;
+
+    // Create mint B
+    let mint_b = create_mint(&mut banks_client, &payer, None).await.unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            seed,
+            LAMPORTS_PER_SOL,
+            100,
+            false,
+            ExpiryMode::Never,
+            None,
+            None,
+            0,
+            Default::default(),
+            None,
+            None,
+            None,
+            None,
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Verify the Escrow holds the deposit on top of its rent
+    let (escrow_pubkey, _) = Pubkey::find_program_address(
+        &[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()],
+        &anchor_escrow::id(),
+    );
+
+    let escrow = banks_client
+        .get_account(escrow_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let rent = banks_client.get_rent().await.unwrap().minimum_balance(escrow.data.len());
+    assert_eq!(escrow.lamports, rent + LAMPORTS_PER_SOL);
+
+    let escrow_account = Escrow::try_deserialize(&mut escrow.data.as_ref()).unwrap();
+    assert_eq!(escrow_account.mint_a, mint_a);
+
+    let vault = spl_associated_token_account::get_associated_token_address(&escrow_pubkey, &mint_a);
+    assert!(banks_client.get_account(vault).await.unwrap().is_none(), "Vault should not be created");
+}
+
+// An Escrow must both deposit and ask for something: a zero receive would let
+// anyone take the vault for free.
+
+#[tokio::test]
+async fn make_error_invalid_amount() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), 2 * LAMPORTS_PER_SOL).await;
+
+    // Create mints
+    let mint_a = create_mint(&mut banks_client, &payer, None).await.unwrap();
+    let mint_b = create_mint(&mut banks_client, &payer, None).await.unwrap();
+
+    // Create and mint tokens to maker's account
+    let _ = create_and_mint_to_token_account(&mut banks_client, mint_a, &payer, maker.pubkey(), 100_000).await;
 
-}
\ No newline at end of file
+    for (deposit, receive) in [(100, 0), (0, 100)] {
+        let mut transaction = Transaction::new_with_payer(
+            &[make(
+                anchor_escrow::id(),
+                spl_token::id(),
+                spl_token::id(),
+                rand::thread_rng().gen(),
+                deposit,
+                receive,
+                false,
+                ExpiryMode::Never,
+                None,
+                None,
+                0,
+                Default::default(),
+                None,
+                None,
+                None,
+                None,
+                maker.pubkey(),
+                mint_a,
+                mint_b,
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &maker], recent_blockhash);
+
+        let result = banks_client.process_transaction(transaction).await;
+        assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidAmount);
+    }
+}
@@ -0,0 +1,126 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, mint_policy, state::{ExpiryMode, TimeSpec}},
+    helpers::{*, spl_token_2022_helpers::*},
+    rand::Rng,
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::Instruction,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+    },
+    spl_token_2022::{extension::ExtensionType, instruction as token_instruction},
+};
+
+// Creates a Token-2022 mint A with the given extensions and funds the maker with it,
+// then builds the Make instruction with the maker's policy.
+
+async fn setup_make(
+    extensions: &[ExtensionType],
+    initialize_extensions: impl FnOnce(&Pubkey) -> Vec<Instruction>,
+    freeze_authority: Option<&Pubkey>,
+    allowed_mint_flags: u8,
+) -> (ProgramTestContext, Keypair, Instruction) {
+    let mut context = program_test(200_000).start_with_context().await;
+    let (banks_client, payer) = (&mut context.banks_client, &context.payer);
+
+    let maker = Keypair::new();
+    let seed: u64 = rand::thread_rng().gen();
+
+    // Airdrop SOL to maker
+    let _ = airdrop(banks_client, payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mint_a = create_mint_with_extensions(banks_client, payer, extensions, initialize_extensions, freeze_authority).await.unwrap();
+    let mint_b = create_mint_with_extensions(banks_client, payer, &[], |_| vec![], None).await.unwrap();
+    create_and_mint_to_token_2022_account(banks_client, mint_a, payer, maker.pubkey(), 1_000).await.unwrap();
+
+    let instruction = make(
+        anchor_escrow::id(),
+        spl_token_2022::id(),
+        spl_token_2022::id(),
+        seed,
+        1_000,
+        500,
+        false,
+        ExpiryMode::Timestamp(TimeSpec::After(3_600)),
+        None,
+        None,
+        allowed_mint_flags,
+        Default::default(),
+        None,
+        None,
+        None,
+        None,
+        maker.pubkey(),
+        mint_a,
+        mint_b,
+    );
+
+    (context, maker, instruction)
+}
+
+// Trying to make an Escrow of a mint with a permanent delegate the maker did not allow.
+
+#[tokio::test]
+async fn make_error_permanent_delegate_not_allowed() {
+    let delegate = Pubkey::new_unique();
+    let (mut context, maker, instruction) = setup_make(
+        &[ExtensionType::PermanentDelegate],
+        |mint| vec![token_instruction::initialize_permanent_delegate(&spl_token_2022::id(), mint, &delegate).unwrap()],
+        None,
+        0,
+    ).await;
+
+    let result = process(&mut context, instruction, &[&maker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::PermanentDelegateNotAllowed);
+}
+
+// Making an Escrow of a mint with a permanent delegate the maker allowed.
+
+#[tokio::test]
+async fn successful_make_with_allowed_permanent_delegate() {
+    let delegate = Pubkey::new_unique();
+    let (mut context, maker, instruction) = setup_make(
+        &[ExtensionType::PermanentDelegate],
+        |mint| vec![token_instruction::initialize_permanent_delegate(&spl_token_2022::id(), mint, &delegate).unwrap()],
+        None,
+        mint_policy::PERMANENT_DELEGATE,
+    ).await;
+
+    process(&mut context, instruction, &[&maker]).await.unwrap();
+}
+
+// Trying to make an Escrow of a mint with a freeze authority the maker did not allow.
+
+#[tokio::test]
+async fn make_error_freeze_authority_not_allowed() {
+    let freeze_authority = Pubkey::new_unique();
+    let (mut context, maker, instruction) = setup_make(
+        &[],
+        |_| vec![],
+        Some(&freeze_authority),
+        mint_policy::PERMANENT_DELEGATE | mint_policy::CLOSE_AUTHORITY,
+    ).await;
+
+    let result = process(&mut context, instruction, &[&maker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::FreezeAuthorityNotAllowed);
+}
+
+// Trying to make an Escrow of a mint that its close authority could close.
+
+#[tokio::test]
+async fn make_error_close_authority_not_allowed() {
+    let close_authority = Pubkey::new_unique();
+    let (mut context, maker, instruction) = setup_make(
+        &[ExtensionType::MintCloseAuthority],
+        |mint| vec![token_instruction::initialize_mint_close_authority(&spl_token_2022::id(), mint, Some(&close_authority)).unwrap()],
+        None,
+        0,
+    ).await;
+
+    let result = process(&mut context, instruction, &[&maker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::CloseAuthorityNotAllowed);
+}
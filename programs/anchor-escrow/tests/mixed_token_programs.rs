@@ -0,0 +1,168 @@
+mod helpers;
+
+use {
+    anchor_escrow::state::{ExpiryMode, TimeSpec},
+    helpers::{*, spl_token_helpers::*, spl_token_2022_helpers::*},
+    rand::Rng,
+    solana_program_test::*,
+    solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    },
+    spl_associated_token_account::get_associated_token_address_with_program_id,
+};
+
+// Swapping a legacy SPL Token mint A for a Token-2022 mint B with a 1% transfer fee,
+// each leg going through its own token program.
+
+#[tokio::test]
+async fn successful_make_and_take_across_token_programs() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(400_000);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let seed: u64 = rand::thread_rng().gen();
+
+    // Airdrop SOL to maker and taker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    // Create mint A on SPL Token and mint B on Token-2022
+    let mint_a = create_mint(&mut banks_client, &payer, None).await.unwrap();
+    let mint_b = create_mint_with_transfer_fee(&mut banks_client, &payer, 100, u64::MAX).await.unwrap();
+    create_and_mint_to_token_account(&mut banks_client, mint_a, &payer, maker.pubkey(), 1_000).await.unwrap();
+    create_and_mint_to_token_2022_account(&mut banks_client, mint_b, &payer, taker.pubkey(), 1_000).await.unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token_2022::id(),
+            seed,
+            1_000,
+            500,
+            false,
+            ExpiryMode::Timestamp(TimeSpec::After(3_600)),
+            None,
+            None,
+            0,
+            Default::default(),
+            None,
+            None,
+            None,
+            None,
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let (escrow_pubkey, _) = Pubkey::find_program_address(
+        &[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()],
+        &anchor_escrow::id(),
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token_2022::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            1_000,
+            500,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let taker_ata_a = get_associated_token_address_with_program_id(&taker.pubkey(), &mint_a, &spl_token::id());
+    assert_eq!(get_token_balance(&mut banks_client, taker_ata_a).await.unwrap(), 1_000, "Taker should receive mint A");
+
+    let maker_ata_b = get_associated_token_address_with_program_id(&maker.pubkey(), &mint_b, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, maker_ata_b).await.unwrap(), 495, "Maker should receive mint B net of the transfer fee");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed");
+}
+
+// Trying to make an Escrow with mint B passed under the wrong token program.
+
+#[tokio::test]
+async fn make_error_wrong_token_program() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(400_000);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let maker = Keypair::new();
+    let seed: u64 = rand::thread_rng().gen();
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mint_a = create_mint(&mut banks_client, &payer, None).await.unwrap();
+    let mint_b = create_mint_with_transfer_fee(&mut banks_client, &payer, 100, u64::MAX).await.unwrap();
+    create_and_mint_to_token_account(&mut banks_client, mint_a, &payer, maker.pubkey(), 1_000).await.unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            seed,
+            1_000,
+            500,
+            false,
+            ExpiryMode::Timestamp(TimeSpec::After(3_600)),
+            None,
+            None,
+            0,
+            Default::default(),
+            None,
+            None,
+            None,
+            None,
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err(), "Make should reject mint B under SPL Token");
+}
@@ -0,0 +1,366 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, Expiry, ExpiryMode, NftTerms}}, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Testing the NFT mode of the Escrow: makers offering an NFT, and makers asking
+// for an NFT of a verified Metaplex collection, checked against the metadata
+// account passed by the taker.
+
+// Making an Escrow offering an NFT for tokens.
+
+#[tokio::test]
+async fn successful_make_nft_for_token() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let seed: u64 = rand::thread_rng().gen();
+
+    // Setup the NFT owned by the maker
+    add_nft_mint(&mut test, mint_a);
+    add_mint(&mut test, mint_b, 100_000);
+    add_token_account(&mut test, mint_a, maker.pubkey(), 1);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            seed,
+            1,
+            500,
+            false,
+            ExpiryMode::Never,
+            None,
+            None,
+            0,
+            NftTerms { nft_a: true, ..Default::default() },
+            None,
+            None,
+            None,
+            None,
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let (escrow_pubkey, _) = Pubkey::find_program_address(
+        &[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()],
+        &anchor_escrow::id(),
+    );
+    let vault = spl_associated_token_account::get_associated_token_address(&escrow_pubkey, &mint_a);
+    let vault_balance = get_token_balance(&mut banks_client, vault).await.unwrap();
+    assert_eq!(vault_balance, 1, "Vault should hold the NFT");
+}
+
+// Trying to make an Escrow offering a fungible mint as an NFT.
+
+#[tokio::test]
+async fn make_error_not_an_nft() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_mint(&mut test, mint_b, 100_000);
+    add_token_account(&mut test, mint_a, maker.pubkey(), 100_000);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            rand::thread_rng().gen(),
+            1,
+            500,
+            false,
+            ExpiryMode::Never,
+            None,
+            None,
+            0,
+            NftTerms { nft_a: true, ..Default::default() },
+            None,
+            None,
+            None,
+            None,
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::NotAnNft);
+}
+
+// Setting up a collection bid of tokens asking for `receive` NFTs of `collection`,
+// and the NFTs of the taker.
+
+fn setup_collection_bid(test: &mut ProgramTest, maker: &Keypair, taker: &Keypair, mint_a: Pubkey, collection: Pubkey, receive: u64, nfts: &[Pubkey]) -> Pubkey {
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b: collection,
+        amount: 100 * receive,
+        receive,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: NftTerms { nft_a: false, nft_b: true, collection_b: true },
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(test, mint_a, 100_000);
+    add_token_account(test, mint_a, escrow_pubkey, 100 * receive);
+
+    for nft in nfts {
+        add_nft_mint(test, *nft);
+        add_token_account(test, *nft, taker.pubkey(), 1);
+    }
+
+    escrow_pubkey
+}
+
+// Processes a Take of the collection Escrow, passing `metadata_b` for the NFT.
+
+async fn take_nft(mut test: ProgramTest, maker: &Keypair, taker: &Keypair, mint_a: Pubkey, mint_b: Pubkey, escrow: Pubkey, metadata_b: Option<Pubkey>) -> (BanksClient, Result<(), BanksClientError>) {
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow,
+            fee_recipient,
+            None,
+            metadata_b,
+            None,
+            0,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, taker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    (banks_client, result)
+}
+
+// Taking an Escrow asking for an NFT of a collection with an NFT whose metadata
+// shows a verified membership.
+
+#[tokio::test]
+async fn successful_take_nft_of_collection() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let collection = Keypair::new().pubkey();
+
+    let escrow_pubkey = setup_collection_bid(&mut test, &maker, &taker, mint_a, collection, 1, &[mint_b]);
+    let metadata_b = add_metadata(&mut test, mint_b, Some((collection, true)));
+
+    let (mut banks_client, result) = take_nft(test, &maker, &taker, mint_a, mint_b, escrow_pubkey, Some(metadata_b)).await;
+    result.unwrap();
+
+    let maker_ata_b = spl_associated_token_account::get_associated_token_address(&maker.pubkey(), &mint_b);
+    let maker_nft_balance = get_token_balance(&mut banks_client, maker_ata_b).await.unwrap();
+    assert_eq!(maker_nft_balance, 1, "Maker should receive the NFT");
+
+    let taker_ata_a = spl_associated_token_account::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let taker_balance = get_token_balance(&mut banks_client, taker_ata_a).await.unwrap();
+    assert_eq!(taker_balance, 100, "Taker should receive 100 tokens of mint A");
+}
+
+// Trying to take a collection Escrow with an NFT whose membership is unverified.
+
+#[tokio::test]
+async fn take_error_collection_not_verified() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let collection = Keypair::new().pubkey();
+
+    let escrow_pubkey = setup_collection_bid(&mut test, &maker, &taker, mint_a, collection, 1, &[mint_b]);
+    let metadata_b = add_metadata(&mut test, mint_b, Some((collection, false)));
+
+    let (_, result) = take_nft(test, &maker, &taker, mint_a, mint_b, escrow_pubkey, Some(metadata_b)).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::CollectionNotVerified);
+}
+
+// Trying to take a collection Escrow without passing the metadata of the NFT.
+
+#[tokio::test]
+async fn take_error_metadata_required() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let collection = Keypair::new().pubkey();
+
+    let escrow_pubkey = setup_collection_bid(&mut test, &maker, &taker, mint_a, collection, 1, &[mint_b]);
+
+    let (_, result) = take_nft(test, &maker, &taker, mint_a, mint_b, escrow_pubkey, None).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::MetadataRequired);
+}
+
+// Filling a floor bid for two NFTs of a collection one NFT at a time, each fill
+// releasing half of the tokens, with the Escrow closed after the last one.
+
+#[tokio::test]
+async fn successful_floor_bid_filled_per_nft() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let collection = Keypair::new().pubkey();
+    let nfts = [Keypair::new().pubkey(), Keypair::new().pubkey()];
+
+    let escrow_pubkey = setup_collection_bid(&mut test, &maker, &taker, mint_a, collection, 2, &nfts);
+    let metadata = nfts.map(|nft| add_metadata(&mut test, nft, Some((collection, true))));
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let taker_ata_a = spl_associated_token_account::get_associated_token_address(&taker.pubkey(), &mint_a);
+
+    for (filled, (nft, metadata_b)) in nfts.into_iter().zip(metadata).enumerate() {
+        let mut transaction = Transaction::new_with_payer(
+            &[take_partial(
+                anchor_escrow::id(),
+                spl_token::id(),
+                spl_token::id(),
+                taker.pubkey(),
+                maker.pubkey(),
+                mint_a,
+                nft,
+                escrow_pubkey,
+                fee_recipient,
+                None,
+                Some(metadata_b),
+                None,
+                1,
+                0,
+                u64::MAX,
+                vec![],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &taker], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let maker_ata_b = spl_associated_token_account::get_associated_token_address(&maker.pubkey(), &nft);
+        assert_eq!(get_token_balance(&mut banks_client, maker_ata_b).await.unwrap(), 1, "Maker should receive the NFT");
+
+        let taker_balance = get_token_balance(&mut banks_client, taker_ata_a).await.unwrap();
+        assert_eq!(taker_balance, 100 * (filled as u64 + 1), "Each NFT should release 100 tokens of mint A");
+    }
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed after the last NFT");
+}
@@ -0,0 +1,243 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, oracle::PYTH_RECEIVER_ID, state::{DutchAuction, Escrow, ExpiryMode, OracleTerms, TimeSpec}}, anchor_lang::AccountDeserialize, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        account::AccountSharedData,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Testing oracle pegged Escrows: takes pay the value of the vault at the price of
+// a Pyth feed plus the maker's spread, never less than the floor in `receive`.
+
+/// Id of the Pyth feed the Escrow is pegged to.
+const FEED_ID: [u8; 32] = [7; 32];
+
+/// Price update written to a feed account, in units of `10^-8` mint B per mint A.
+struct FeedPrice {
+    feed_id: [u8; 32],
+    verified: bool,
+    price: i64,
+    conf: u64,
+    age: i64,
+}
+
+impl FeedPrice {
+    /// A fully verified price of the Escrow's feed.
+    fn new(price: i64, conf: u64, age: i64) -> Self {
+        Self { feed_id: FEED_ID, verified: true, price, conf, age }
+    }
+}
+
+const PRICE_UPDATE_SIZE: usize = 134;
+
+// Writes a `PriceUpdateV2` account with the layout of the Pyth receiver program. A
+// partially verified update records its number of signatures.
+
+fn set_feed(setup: &mut EscrowSetup, feed: Pubkey, owner: Pubkey, price: FeedPrice) {
+    let mut data = vec![34, 241, 35, 99, 157, 126, 244, 205];
+    data.extend_from_slice(&[0; 32]);
+    match price.verified {
+        true => data.push(1),
+        false => data.extend_from_slice(&[0, 3]),
+    }
+    data.extend_from_slice(&price.feed_id);
+    data.extend_from_slice(&price.price.to_le_bytes());
+    data.extend_from_slice(&price.conf.to_le_bytes());
+    data.extend_from_slice(&(-8i32).to_le_bytes());
+    data.extend_from_slice(&(setup.now - price.age).to_le_bytes());
+    data.resize(PRICE_UPDATE_SIZE, 0);
+
+    let mut account = AccountSharedData::new(u32::MAX as u64, PRICE_UPDATE_SIZE, &owner);
+    account.set_data_from_slice(&data);
+    setup.context.set_account(&feed, &account);
+}
+
+// Setting up an Escrow of 200 tokens of mint A pegged to the feed with a 1% premium,
+// a floor of 100 tokens of mint B, prices up to 60 seconds old and a confidence
+// interval up to 1% of the price. The feed prices mint A at 2.5 mint B.
+
+async fn setup() -> (EscrowSetup, Pubkey) {
+    let feed = Keypair::new().pubkey();
+
+    let mut setup = EscrowSetup::start(program_test(200_000), 200, 100, |escrow, _| Escrow {
+        oracle: Some(OracleTerms { feed_id: FEED_ID, spread_bps: 100, max_age: 60, max_conf_bps: 100 }),
+        ..escrow
+    }).await;
+
+    set_feed(&mut setup, feed, PYTH_RECEIVER_ID, FeedPrice::new(250_000_000, 100_000, 0));
+    (setup, feed)
+}
+
+async fn maker_balance_b(setup: &mut EscrowSetup) -> u64 {
+    let maker_ata_b = spl_associated_token_account::get_associated_token_address(&setup.maker.pubkey(), &setup.mint_b);
+    get_token_balance(&mut setup.context.banks_client, maker_ata_b).await.unwrap()
+}
+
+// Taking at the feed price: 200 tokens at 2.5 plus 1% cost 505.
+
+#[tokio::test]
+async fn successful_take_at_oracle_price() {
+    let (mut setup, feed) = setup().await;
+
+    let instruction = setup.take_instruction(Some(feed), u64::MAX);
+    process(&mut setup.context, instruction, &[&setup.taker]).await.unwrap();
+
+    assert_eq!(maker_balance_b(&mut setup).await, 505, "Maker should receive the oracle price plus the spread");
+
+    let escrow_account = setup.context.banks_client.get_account(setup.escrow).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed");
+}
+
+// Taking when the feed values the vault below the floor pays the floor.
+
+#[tokio::test]
+async fn successful_take_at_floor() {
+    let (mut setup, feed) = setup().await;
+    set_feed(&mut setup, feed, PYTH_RECEIVER_ID, FeedPrice::new(25_000_000, 10_000, 0));
+
+    let instruction = setup.take_instruction(Some(feed), u64::MAX);
+    process(&mut setup.context, instruction, &[&setup.taker]).await.unwrap();
+
+    assert_eq!(maker_balance_b(&mut setup).await, 100, "Maker should receive the floor");
+}
+
+// Partially filling at the feed price: the floor is scaled down to what is left.
+
+#[tokio::test]
+async fn successful_take_partial_at_oracle_price() {
+    let (mut setup, feed) = setup().await;
+
+    let instruction = take_partial(
+        anchor_escrow::id(),
+        spl_token::id(),
+        spl_token::id(),
+        setup.taker.pubkey(),
+        setup.maker.pubkey(),
+        setup.mint_a,
+        setup.mint_b,
+        setup.escrow,
+        setup.fee_recipient,
+        None,
+        None,
+        Some(feed),
+        101,
+        0,
+        u64::MAX,
+        vec![],
+    );
+    process(&mut setup.context, instruction, &[&setup.taker]).await.unwrap();
+
+    let taker_ata_a = spl_associated_token_account::get_associated_token_address(&setup.taker.pubkey(), &setup.mint_a);
+    let taker_balance = get_token_balance(&mut setup.context.banks_client, taker_ata_a).await.unwrap();
+    assert_eq!(taker_balance, 40, "Paying a fifth of the price should release a fifth of mint A");
+
+    let account = setup.context.banks_client.get_account(setup.escrow).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(escrow.amount, 160);
+    assert_eq!(escrow.receive, 80, "Floor should be scaled down to what is left");
+}
+
+// Trying to take with a price older than the maker accepts.
+
+#[tokio::test]
+async fn take_error_stale_price() {
+    let (mut setup, feed) = setup().await;
+    set_feed(&mut setup, feed, PYTH_RECEIVER_ID, FeedPrice::new(250_000_000, 100_000, 61));
+
+    let instruction = setup.take_instruction(Some(feed), u64::MAX);
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::StalePrice);
+}
+
+// Trying to take with a confidence interval wider than the maker accepts.
+
+#[tokio::test]
+async fn take_error_price_uncertain() {
+    let (mut setup, feed) = setup().await;
+    set_feed(&mut setup, feed, PYTH_RECEIVER_ID, FeedPrice::new(250_000_000, 5_000_000, 0));
+
+    let instruction = setup.take_instruction(Some(feed), u64::MAX);
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::PriceUncertain);
+}
+
+// Trying to take with an update of another feed, with a look-alike account not owned
+// by the Pyth receiver, or with a partially verified update.
+
+#[tokio::test]
+async fn take_error_invalid_price_feed() {
+    let (mut setup, feed) = setup().await;
+
+    let other_feed = Keypair::new().pubkey();
+    set_feed(&mut setup, other_feed, PYTH_RECEIVER_ID, FeedPrice { feed_id: [8; 32], ..FeedPrice::new(100_000, 0, 0) });
+
+    let instruction = setup.take_instruction(Some(other_feed), u64::MAX);
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidPriceFeed);
+
+    set_feed(&mut setup, feed, Keypair::new().pubkey(), FeedPrice::new(100_000, 0, 0));
+
+    let instruction = setup.take_instruction(Some(feed), u64::MAX);
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidPriceFeed);
+
+    set_feed(&mut setup, feed, PYTH_RECEIVER_ID, FeedPrice { verified: false, ..FeedPrice::new(100_000, 0, 0) });
+
+    let instruction = setup.take_instruction(Some(feed), u64::MAX);
+    let result = process(&mut setup.context, instruction, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidPriceFeed);
+}
+
+// Trying to make an oracle pegged Escrow that is also a Dutch auction.
+
+#[tokio::test]
+async fn make_error_invalid_oracle_terms() {
+    let mut test = program_test(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_mint(&mut test, mint_b, 100_000);
+    add_token_account(&mut test, mint_a, maker.pubkey(), 100_000);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            rand::thread_rng().gen(),
+            100,
+            100,
+            false,
+            ExpiryMode::Timestamp(TimeSpec::After(3_600)),
+            None,
+            None,
+            0,
+            Default::default(),
+            Some(DutchAuction { start_receive: 400, start_time: 0, end_time: 3_600, step: 0 }),
+            None,
+            Some(OracleTerms { feed_id: FEED_ID, spread_bps: 0, max_age: 60, max_conf_bps: 100 }),
+            None,
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidOracleTerms);
+}
@@ -13,8 +13,7 @@ use {
         signer::Signer,
         transaction::Transaction,
     },
-    std::i64,
-    anchor_escrow::Escrow,
+    anchor_escrow::{Escrow, Expiry},
 };
 
 // Testing the Refund instruction using the Bank for creating the Escrow account 
@@ -47,8 +46,17 @@ async fn successful_token_refund() {
         maker: maker.pubkey(),
         mint_a: mint_a.pubkey(),
         mint_b: mint_b.pubkey(),
+        amount: 100_000,
         receive: 100,
-        expiry: i64::MAX,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
         bump,
     };
 
@@ -108,7 +116,9 @@ async fn successful_token_refund() {
     transaction.sign(&[&payer, &maker], recent_blockhash);
 
     // Process the transaction
-    banks_client.process_transaction(transaction).await.unwrap();
+    let result = banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    assert!(result.result.is_ok());
+    assert!(emits_event_cpi(&result.metadata.unwrap().log_messages), "Refund should emit its event through a CPI");
 
     // Verify final balances
     let final_maker_balance = get_token_balance(&mut banks_client, maker_ata).await.unwrap();
@@ -125,4 +135,74 @@ async fn successful_token_refund() {
 
     let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap();
     assert!(escrow_account.is_none(), "Escrow account should be closed after refund");
-}
\ No newline at end of file
+}
+
+// Testing the Refund instruction of an Escrow holding native SOL: closing the
+// Escrow account gives the deposit back to the maker together with the rent.
+
+#[tokio::test]
+async fn successful_native_refund() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let seed: u64 = rand::thread_rng().gen();
+    let mint_a = spl_token::native_mint::id();
+
+    let (_, bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()],
+        &anchor_escrow::id(),
+    );
+
+    let escrow_pubkey = add_escrow(&mut test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b: Keypair::new().pubkey(),
+        amount: LAMPORTS_PER_SOL,
+        receive: 100,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await.unwrap();
+
+    let escrow_lamports = banks_client.get_balance(escrow_pubkey).await.unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[refund(
+            anchor_escrow::id(),
+            spl_token::id(),
+            maker.pubkey(),
+            mint_a,
+            escrow_pubkey,
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let maker_lamports = banks_client.get_balance(maker.pubkey()).await.unwrap();
+    assert_eq!(maker_lamports, LAMPORTS_PER_SOL + escrow_lamports, "Maker should receive the refunded lamports");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed after refund");
+}
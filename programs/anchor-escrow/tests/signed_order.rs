@@ -0,0 +1,214 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{SignedOrder, ORDER_NONCE_WINDOW}}, helpers::{*, spl_token_helpers::*}, solana_program_test::*, solana_sdk::{
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::TransactionError,
+    }
+};
+
+// Testing signed orders: the maker signs an order off-chain and approves the
+// program's delegate, then a taker fills it in a transaction that also has the
+// Ed25519 program verify the maker's signature.
+
+// Setting up the maker with 1_000 tokens of mint A, all of them approved to the
+// delegate, and an order of 100 tokens of mint A for 300 tokens of mint B expiring
+// in an hour. The protocol takes a 1% fee.
+
+async fn setup() -> (EscrowSetup, SignedOrder) {
+    let mut setup = EscrowSetup::start(program_test(200_000), 100, 300, |escrow, _| escrow).await;
+    let maker_ata_a = set_token_account(&mut setup.context, setup.mint_a, setup.maker.pubkey(), 1_000);
+
+    // Replace the fee-free Config with a 1% fee
+    set_config(&mut setup.context, setup.fee_recipient, 100, 0);
+
+    // Maker approves the delegate
+    let (delegate, _) = Pubkey::find_program_address(&[b"delegate"], &anchor_escrow::id());
+    let approve = spl_token::instruction::approve(
+        &spl_token::id(),
+        &maker_ata_a,
+        &delegate,
+        &setup.maker.pubkey(),
+        &[],
+        1_000,
+    ).unwrap();
+    process(&mut setup.context, approve, &[&setup.maker]).await.unwrap();
+
+    let order = SignedOrder {
+        maker: setup.maker.pubkey(),
+        mint_a: setup.mint_a,
+        mint_b: setup.mint_b,
+        amount_a: 100,
+        amount_b: 300,
+        expiry: setup.now + 3_600,
+        taker: None,
+        nonce: 1,
+    };
+
+    (setup, order)
+}
+
+fn sign(signer: &Keypair, order: &SignedOrder) -> [u8; 64] {
+    signer.sign_message(&order.message()).into()
+}
+
+fn fill_instructions(setup: &EscrowSetup, order: SignedOrder, signature: &[u8; 64]) -> [Instruction; 2] {
+    fill_signed_order(
+        anchor_escrow::id(),
+        spl_token::id(),
+        setup.taker.pubkey(),
+        order,
+        signature,
+        setup.fee_recipient,
+    )
+}
+
+async fn cancel(setup: &mut EscrowSetup, nonce: u64) -> Result<(), BanksClientError> {
+    let instruction = cancel_orders_up_to(anchor_escrow::id(), setup.maker.pubkey(), nonce);
+    process(&mut setup.context, instruction, &[&setup.maker]).await
+}
+
+async fn fill(setup: &mut EscrowSetup, order: SignedOrder) -> Result<(), BanksClientError> {
+    let signature = sign(&setup.maker, &order);
+    let instructions = fill_instructions(setup, order, &signature);
+    process_instructions(&mut setup.context, &instructions, &[&setup.taker]).await
+}
+
+// Filling a signed order: the taker gets mint A straight from the maker's token
+// account and pays mint B, less the protocol fee, to the maker.
+
+#[tokio::test]
+async fn successful_fill_signed_order() {
+    let (mut setup, order) = setup().await;
+
+    let signature = sign(&setup.maker, &order);
+    let instructions = fill_instructions(&setup, order, &signature);
+    process_instructions(&mut setup.context, &instructions, &[&setup.taker]).await.unwrap();
+
+    assert_eq!(balance(&mut setup.context, &setup.taker.pubkey(), &order.mint_a).await, 100);
+    assert_eq!(balance(&mut setup.context, &setup.maker.pubkey(), &order.mint_a).await, 900);
+    assert_eq!(balance(&mut setup.context, &setup.maker.pubkey(), &order.mint_b).await, 297);
+    assert_eq!(balance(&mut setup.context, &setup.fee_recipient, &order.mint_b).await, 3);
+    assert_eq!(balance(&mut setup.context, &setup.taker.pubkey(), &order.mint_b).await, 99_700);
+}
+
+// Trying to fill an order whose amounts were changed after the maker signed it.
+
+#[tokio::test]
+async fn fill_error_tampered_order() {
+    let (mut setup, order) = setup().await;
+
+    let signature = sign(&setup.maker, &order);
+    let tampered = SignedOrder { amount_a: 1_000, ..order };
+    let instructions = fill_instructions(&setup, tampered, &signature);
+    let result = process_instructions(&mut setup.context, &instructions, &[&setup.taker]).await;
+
+    // The runtime rejects a failed Ed25519 check before running any instruction
+    assert!(matches!(
+        result.unwrap_err(),
+        BanksClientError::TransactionError(TransactionError::InvalidAccountIndex)
+    ));
+}
+
+// Trying to fill an order with a valid signature from someone other than the maker.
+
+#[tokio::test]
+async fn fill_error_signed_by_another_key() {
+    let (mut setup, order) = setup().await;
+
+    let impostor = Keypair::new();
+    let signature = sign(&impostor, &order);
+    let [mut verify, fill] = fill_instructions(&setup, order, &signature);
+    verify.data = anchor_escrow::ed25519::instruction_data(&impostor.pubkey(), &signature, &order.message());
+
+    let result = process_instructions(&mut setup.context, &[verify, fill], &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidOrderSignature);
+}
+
+// Trying to fill an order without the Ed25519 program instruction.
+
+#[tokio::test]
+async fn fill_error_missing_signature() {
+    let (mut setup, order) = setup().await;
+
+    let [_, fill] = fill_instructions(&setup, order, &[0; 64]);
+    let result = process_instructions(&mut setup.context, &[fill], &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidOrderSignature);
+}
+
+// Trying to fill an order after its expiry.
+
+#[tokio::test]
+async fn fill_error_order_expired() {
+    let (mut setup, order) = setup().await;
+
+    advance_clock(&mut setup.context, 3_601).await;
+
+    let signature = sign(&setup.maker, &order);
+    let instructions = fill_instructions(&setup, order, &signature);
+    let result = process_instructions(&mut setup.context, &instructions, &[&setup.taker]).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowExpired);
+}
+
+// Trying to fill the same signed order twice.
+
+#[tokio::test]
+async fn fill_error_replayed_order() {
+    let (mut setup, order) = setup().await;
+
+    fill(&mut setup, order).await.unwrap();
+
+    // Replay the very same transaction under a new blockhash
+    setup.context.get_new_latest_blockhash().await.unwrap();
+    let result = fill(&mut setup, order).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::OrderNonceUsed);
+}
+
+// Trying to fill an order the maker cancelled, while later orders stay fillable.
+
+#[tokio::test]
+async fn fill_error_cancelled_order() {
+    let (mut setup, order) = setup().await;
+
+    cancel(&mut setup, order.nonce).await.unwrap();
+
+    let result = fill(&mut setup, order).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::OrderCancelled);
+
+    fill(&mut setup, SignedOrder { nonce: order.nonce + 1, ..order }).await.unwrap();
+}
+
+// Trying to cancel orders without moving the minimum nonce forward.
+
+#[tokio::test]
+async fn cancel_error_invalid_nonce() {
+    let (mut setup, _) = setup().await;
+
+    cancel(&mut setup, 5).await.unwrap();
+
+    let result = cancel(&mut setup, 3).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidNonce);
+}
+
+// Trying to fill a nonce past the window, which would otherwise spend the maker's
+// older open orders. It becomes fillable once the maker cancels up to it.
+
+#[tokio::test]
+async fn fill_error_nonce_past_window() {
+    let (mut setup, order) = setup().await;
+    let far_order = SignedOrder { nonce: order.nonce + ORDER_NONCE_WINDOW, ..order };
+
+    let result = fill(&mut setup, far_order).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidNonce);
+
+    fill(&mut setup, order).await.unwrap();
+
+    cancel(&mut setup, order.nonce + 1).await.unwrap();
+
+    // Retry the very same transaction under a new blockhash
+    setup.context.get_new_latest_blockhash().await.unwrap();
+    fill(&mut setup, far_order).await.unwrap();
+}
@@ -0,0 +1,148 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, Expiry, ExpiryMode, TimeSpec}}, anchor_lang::{prelude::Clock, AccountDeserialize}, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        instruction::Instruction,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Testing staged Escrows: an Escrow with a start time can't be taken before it,
+// and makers set it either as a Unix timestamp or as seconds after make.
+
+// Setting up an Escrow of 100 tokens of mint A for 100 tokens of mint B that
+// becomes fillable 100 seconds from now.
+
+async fn setup() -> EscrowSetup {
+    EscrowSetup::start(program_test(200_000), 100, 100, |escrow, clock| Escrow {
+        start_at: clock.unix_timestamp + 100,
+        ..escrow
+    }).await
+}
+
+async fn process_take(setup: &mut EscrowSetup) -> Result<(), BanksClientError> {
+    let instruction = setup.take_instruction(None, u64::MAX);
+    process(&mut setup.context, instruction, &[&setup.taker]).await
+}
+
+// Trying to take an Escrow before its start time.
+
+#[tokio::test]
+async fn take_error_escrow_not_started() {
+    let mut setup = setup().await;
+
+    let result = process_take(&mut setup).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowNotStarted);
+}
+
+// Taking an Escrow once its start time has passed.
+
+#[tokio::test]
+async fn successful_take_after_start() {
+    let mut setup = setup().await;
+
+    advance_clock(&mut setup.context, 100).await;
+
+    process_take(&mut setup).await.unwrap();
+
+    let escrow_account = setup.context.banks_client.get_account(setup.escrow).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed");
+}
+
+fn make_instruction(maker: &Keypair, mint_a: Pubkey, mint_b: Pubkey, start_at: TimeSpec) -> Instruction {
+    make(
+        anchor_escrow::id(),
+        spl_token::id(),
+        spl_token::id(),
+        rand::thread_rng().gen(),
+        100,
+        100,
+        false,
+        ExpiryMode::Timestamp(TimeSpec::After(3_600)),
+        None,
+        None,
+        0,
+        Default::default(),
+        None,
+        None,
+        None,
+        Some(start_at),
+        maker.pubkey(),
+        mint_a,
+        mint_b,
+    )
+}
+
+// Making staged Escrows with an absolute and a relative start time.
+
+#[tokio::test]
+async fn successful_make_with_start_time() {
+    let mut test = program_test(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_mint(&mut test, mint_b, 100_000);
+    add_token_account(&mut test, mint_a, maker.pubkey(), 100_000);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let now = banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+
+    for (start_at, expected) in [(TimeSpec::At(now + 1_000), Some(now + 1_000)), (TimeSpec::After(60), None)] {
+        let instruction = make_instruction(&maker, mint_a, mint_b, start_at);
+        let escrow_pubkey = instruction.accounts[4].pubkey;
+
+        let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &maker], recent_blockhash);
+
+        let result = banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+        assert!(result.result.is_ok());
+
+        let account = banks_client.get_account(escrow_pubkey).await.unwrap().unwrap();
+        let escrow = Escrow::try_deserialize(&mut account.data.as_ref()).unwrap();
+
+        // A relative start time counts from make, an hour before the Escrow expires
+        let Expiry::Timestamp(expiry) = escrow.expiry else { panic!("Escrow should expire at a timestamp") };
+        let expected = expected.unwrap_or(expiry - 3_600 + 60);
+        assert_eq!(escrow.start_at, expected, "Start time should resolve from the maker's choice");
+    }
+}
+
+// Trying to make an Escrow starting after it expires.
+
+#[tokio::test]
+async fn make_error_invalid_start_time() {
+    let mut test = program_test(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_mint(&mut test, mint_b, 100_000);
+    add_token_account(&mut test, mint_a, maker.pubkey(), 100_000);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make_instruction(&maker, mint_a, mint_b, TimeSpec::After(3_600))],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidStartTime);
+}
@@ -1,7 +1,7 @@
 mod helpers;
 
 use {
-    anchor_escrow::state::Escrow, anchor_lang::prelude::*, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program::program_pack::Pack, solana_program_test::*, solana_sdk::{
+    anchor_escrow::{errors::EscrowErrors, merkle, state::{Escrow, Expiry}}, anchor_lang::prelude::*, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program::program_pack::Pack, solana_program_test::*, solana_sdk::{
         account::{Account as SolanaAccount, AccountSharedData},
         native_token::LAMPORTS_PER_SOL,
         program_option::COption,
@@ -40,8 +40,17 @@ async fn successful_take() {
         maker: maker.pubkey(),
         mint_a,
         mint_b,
+        amount: 100,
         receive: 100,
-        expiry: i64::MAX,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
         bump,
     };
     escrow.try_serialize(&mut escrow_data).unwrap();
@@ -106,26 +115,41 @@ async fn successful_take() {
         );
     }
 
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
     let (mut banks_client, payer, recent_blockhash) = test.start().await;
 
     // Airdrop SOL to taker
-    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), 1 * LAMPORTS_PER_SOL).await;
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
 
     // Execute take instruction
     let mut transaction = Transaction::new_with_payer(
         &[take(
             anchor_escrow::id(),
             spl_token::id(),
+            spl_token::id(),
             taker.pubkey(),
             maker.pubkey(),
             mint_a,
             mint_b,
             escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            0,
+            u64::MAX,
+            vec![],
         )],
         Some(&payer.pubkey()),
     );
     transaction.sign(&[&payer, &taker], recent_blockhash);
-    banks_client.process_transaction(transaction).await.unwrap();
+
+    let result = banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    assert!(result.result.is_ok());
+    assert!(emits_event_cpi(&result.metadata.unwrap().log_messages), "Take should emit its event through a CPI");
 
     // Verify final states
     let vault_account = banks_client.get_account(vault).await.unwrap();
@@ -214,10 +238,14 @@ async fn take_error_escrow_expired() {
         );
     }
 
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
     let mut context = test.start_with_context().await;
 
     // Airdrop SOL to taker
-    let _ = airdrop(&mut context.banks_client, &context.payer, &taker.pubkey(), 1 * LAMPORTS_PER_SOL).await;
+    let _ = airdrop(&mut context.banks_client, &context.payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
 
     // Get the current timestamp
     let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
@@ -229,8 +257,17 @@ async fn take_error_escrow_expired() {
         maker: maker.pubkey(),
         mint_a,
         mint_b,
+        amount: 100,
         receive: 100,
-        expiry: current_time,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Timestamp(current_time),
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
         bump,
     };
 
@@ -254,11 +291,19 @@ async fn take_error_escrow_expired() {
         &[take(
             anchor_escrow::id(),
             spl_token::id(),
+            spl_token::id(),
             taker.pubkey(),
             maker.pubkey(),
             mint_a,
             mint_b,
             escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            0,
+            u64::MAX,
+            vec![],
         )],
         Some(&context.payer.pubkey()),
     );
@@ -266,7 +311,7 @@ async fn take_error_escrow_expired() {
     
     // Process the transaction and expect an error
     let result = context.banks_client.process_transaction(transaction).await;
-    assert_escrow_error(result.unwrap_err(), anchor_escrow::errors::EscrowErrors::EscrowExpired);
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowExpired);
 
     // Verify that the escrow and vault accounts still exist
     let escrow_account = context.banks_client.get_account(escrow_pubkey).await.unwrap();
@@ -274,4 +319,456 @@ async fn take_error_escrow_expired() {
 
     let vault_account = context.banks_client.get_account(vault).await.unwrap();
     assert!(vault_account.is_some(), "Vault account should still exist");
-}
\ No newline at end of file
+}
+// Setting up a private Escrow through the helpers, restricted either to a designated
+// taker or to the takers of an allowlist.
+
+fn setup_private_escrow(test: &mut ProgramTest, maker: &Keypair, taker: &Keypair, mint_a: Pubkey, mint_b: Pubkey, designated: Option<Pubkey>, allowlist: Option<[u8; 32]>) -> Pubkey {
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b,
+        amount: 100,
+        receive: 100,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: designated,
+        allowlist,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(test, mint_a, 100_000);
+    add_mint(test, mint_b, 100_000);
+    add_token_account(test, mint_b, taker.pubkey(), 100_000);
+    add_token_account(test, mint_a, escrow_pubkey, 100);
+
+    escrow_pubkey
+}
+
+// Taking an Escrow restricted to an allowlist of two takers, proving the
+// membership of the taker with the leaf of the other one.
+
+#[tokio::test]
+async fn successful_take_with_allowlist() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let other_taker = Keypair::new().pubkey();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+
+    // Build a two-leaves allowlist
+    let taker_leaf = merkle::leaf(taker.pubkey().as_ref());
+    let other_leaf = merkle::leaf(other_taker.as_ref());
+    let (left, right) = if taker_leaf <= other_leaf { (taker_leaf, other_leaf) } else { (other_leaf, taker_leaf) };
+    let root = solana_program::hash::hashv(&[&[1u8], &left, &right]).to_bytes();
+
+    let escrow_pubkey = setup_private_escrow(&mut test, &maker, &taker, mint_a, mint_b, None, Some(root));
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            0,
+            u64::MAX,
+            vec![other_leaf],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let taker_mint_a = spl_associated_token_account::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let taker_mint_a_balance = get_token_balance(&mut banks_client, taker_mint_a).await.unwrap();
+    assert_eq!(taker_mint_a_balance, 100, "Taker should receive 100 tokens of mint A");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed");
+}
+
+// Trying to take an Escrow reserved to a different designated taker.
+
+#[tokio::test]
+async fn take_error_taker_not_allowed() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let escrow_pubkey = setup_private_escrow(&mut test, &maker, &taker, mint_a, mint_b, Some(Keypair::new().pubkey()), None);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            0,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::TakerNotAllowed);
+}
+
+// Taking an Escrow with a 10% fee, half of which goes to the referrer of the fill.
+
+#[tokio::test]
+async fn successful_take_with_fees() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let referrer = Keypair::new().pubkey();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let escrow_pubkey = setup_private_escrow(&mut test, &maker, &taker, mint_a, mint_b, None, None);
+    let referrer_mint_b = add_token_account(&mut test, mint_b, referrer, 0);
+
+    // Setup config account with a 10% fee and a 50% referral share
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 1_000, 5_000);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            Some(referrer),
+            None,
+            None,
+            0,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+
+    let result = banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    assert!(result.result.is_ok());
+
+    let maker_mint_b = spl_associated_token_account::get_associated_token_address(&maker.pubkey(), &mint_b);
+    let maker_mint_b_balance = get_token_balance(&mut banks_client, maker_mint_b).await.unwrap();
+    assert_eq!(maker_mint_b_balance, 90, "Maker should receive 90 tokens of mint B");
+
+    let fee_recipient_mint_b = spl_associated_token_account::get_associated_token_address(&fee_recipient, &mint_b);
+    let fee_recipient_mint_b_balance = get_token_balance(&mut banks_client, fee_recipient_mint_b).await.unwrap();
+    assert_eq!(fee_recipient_mint_b_balance, 5, "Fee recipient should receive 5 tokens of mint B");
+
+    let referrer_mint_b_balance = get_token_balance(&mut banks_client, referrer_mint_b).await.unwrap();
+    assert_eq!(referrer_mint_b_balance, 5, "Referrer should receive 5 tokens of mint B");
+}
+
+// Taking an Escrow offering native SOL held by the Escrow account for tokens of mint B.
+
+#[tokio::test]
+async fn successful_take_native_sol_for_token() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = spl_token::native_mint::id();
+    let mint_b = Keypair::new().pubkey();
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(&mut test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b,
+        amount: LAMPORTS_PER_SOL,
+        receive: 100,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(&mut test, mint_b, 100_000);
+    add_token_account(&mut test, mint_b, taker.pubkey(), 100_000);
+    let maker_mint_b = add_token_account(&mut test, mint_b, maker.pubkey(), 0);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+    add_token_account(&mut test, mint_b, fee_recipient, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let escrow_lamports = banks_client.get_balance(escrow_pubkey).await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[take(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            0,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let taker_lamports = banks_client.get_balance(taker.pubkey()).await.unwrap();
+    assert_eq!(taker_lamports, 2 * LAMPORTS_PER_SOL, "Taker should receive the escrowed lamports");
+
+    let maker_lamports = banks_client.get_balance(maker.pubkey()).await.unwrap();
+    assert_eq!(maker_lamports, escrow_lamports - LAMPORTS_PER_SOL, "Maker should receive the rest of the Escrow account");
+
+    let maker_mint_b_balance = get_token_balance(&mut banks_client, maker_mint_b).await.unwrap();
+    assert_eq!(maker_mint_b_balance, 100, "Maker should receive 100 tokens of mint B");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed");
+}
+
+// Taking an Escrow asking for native SOL, paid straight to the maker's wallet.
+
+#[tokio::test]
+async fn successful_take_token_for_native_sol() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = spl_token::native_mint::id();
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(&mut test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b,
+        amount: 100,
+        receive: LAMPORTS_PER_SOL / 2,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_token_account(&mut test, mint_a, escrow_pubkey, 100);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let escrow_lamports = banks_client.get_balance(escrow_pubkey).await.unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            0,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let maker_lamports = banks_client.get_balance(maker.pubkey()).await.unwrap();
+    assert_eq!(maker_lamports, LAMPORTS_PER_SOL / 2 + escrow_lamports, "Maker should receive the lamports and the Escrow rent");
+
+    let taker_mint_a = spl_associated_token_account::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let taker_mint_a_balance = get_token_balance(&mut banks_client, taker_mint_a).await.unwrap();
+    assert_eq!(taker_mint_a_balance, 100, "Taker should receive 100 tokens of mint A");
+}
+
+// Trying to take an Escrow asking for more than the taker's slippage bounds allow.
+
+#[tokio::test]
+async fn take_error_slippage_exceeded() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let escrow_pubkey = setup_private_escrow(&mut test, &maker, &taker, mint_a, mint_b, None, None);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    for (min_amount_a, max_amount_b) in [(101, 100), (100, 99)] {
+        let mut transaction = Transaction::new_with_payer(
+            &[take(
+                anchor_escrow::id(),
+                spl_token::id(),
+                spl_token::id(),
+                taker.pubkey(),
+                maker.pubkey(),
+                mint_a,
+                mint_b,
+                escrow_pubkey,
+                fee_recipient,
+                None,
+                None,
+                None,
+                min_amount_a,
+                max_amount_b,
+                vec![],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &taker], recent_blockhash);
+
+        let result = banks_client.process_transaction(transaction).await;
+        assert_escrow_error(result.unwrap_err(), EscrowErrors::SlippageExceeded);
+    }
+}
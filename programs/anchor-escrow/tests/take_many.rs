@@ -0,0 +1,151 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, merkle, state::{Escrow, Expiry}}, anchor_lang::AccountDeserialize, helpers::{*, spl_token_helpers::*}, solana_program_test::*, solana_sdk::{
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+    }
+};
+
+// Testing take_many: one instruction fills several Escrows trading the same mints,
+// and fails as a whole when any of them can't be filled.
+
+// Setting up an Escrow directly in the running Bank, with its vault holding `amount`.
+
+fn setup_escrow(setup: &mut EscrowSetup, maker: Pubkey, amount: u64, receive: u64, expiry: Expiry) -> (Pubkey, Pubkey) {
+    let escrow = set_escrow(&mut setup.context, &Escrow {
+        expiry,
+        ..default_escrow(maker, setup.mint_a, setup.mint_b, amount, receive)
+    });
+    set_token_account(&mut setup.context, setup.mint_a, escrow, amount);
+
+    (escrow, maker)
+}
+
+// Setting up three Escrows of mint A for mint B: Alice, the maker of the shared
+// setup, offers 100 for 200 and 30 for 60, Bob offers 50 for 150, his Escrow
+// expiring as set. Returns Bob and the (escrow, maker) pairs. The protocol takes
+// a 1% fee.
+
+async fn setup(bob_expiry: Expiry) -> (EscrowSetup, Pubkey, Vec<(Pubkey, Pubkey)>) {
+    let mut setup = EscrowSetup::start(program_test(400_000), 100, 200, |escrow, _| escrow).await;
+    let (alice, bob) = (setup.maker.pubkey(), Keypair::new().pubkey());
+
+    let escrows = vec![
+        (setup.escrow, alice),
+        setup_escrow(&mut setup, bob, 50, 150, bob_expiry),
+        setup_escrow(&mut setup, alice, 30, 60, Expiry::Never),
+    ];
+
+    // Replace the fee-free Config with a 1% fee
+    set_config(&mut setup.context, setup.fee_recipient, 100, 0);
+
+    (setup, bob, escrows)
+}
+
+async fn fill(setup: &mut EscrowSetup, escrows: &[(Pubkey, Pubkey)], min_amount_a: u64, max_amount_b: u64) -> Result<(), BanksClientError> {
+    let instruction = take_many(
+        anchor_escrow::id(),
+        spl_token::id(),
+        setup.taker.pubkey(),
+        setup.mint_a,
+        setup.mint_b,
+        setup.fee_recipient,
+        escrows,
+        min_amount_a,
+        max_amount_b,
+    );
+    process(&mut setup.context, instruction, &[&setup.taker]).await
+}
+
+// Filling three Escrows at once: the taker gets all of mint A, each maker the
+// price of its Escrows less the fee, and every Escrow and vault is closed.
+
+#[tokio::test]
+async fn successful_take_many() {
+    let (mut setup, bob, escrows) = setup(Expiry::Never).await;
+
+    fill(&mut setup, &escrows, 180, 410).await.unwrap();
+
+    let (taker, alice) = (setup.taker.pubkey(), setup.maker.pubkey());
+    assert_eq!(balance(&mut setup.context, &taker, &setup.mint_a).await, 180);
+    assert_eq!(balance(&mut setup.context, &taker, &setup.mint_b).await, 99_590);
+    assert_eq!(balance(&mut setup.context, &alice, &setup.mint_b).await, 198 + 60);
+    assert_eq!(balance(&mut setup.context, &bob, &setup.mint_b).await, 149);
+    assert_eq!(balance(&mut setup.context, &setup.fee_recipient, &setup.mint_b).await, 3);
+
+    for (escrow, _) in escrows {
+        let escrow_account = setup.context.banks_client.get_account(escrow).await.unwrap();
+        assert!(escrow_account.is_none(), "Escrow account should be closed");
+
+        let vault = token_account(&escrow, &setup.mint_a, &spl_token::id()).unwrap();
+        let vault_account = setup.context.banks_client.get_account(vault).await.unwrap();
+        assert!(vault_account.is_none(), "Vault should be closed");
+    }
+}
+
+// Trying to fill several Escrows when one has expired: none of them is filled.
+
+#[tokio::test]
+async fn take_many_error_one_escrow_expired() {
+    let (mut setup, _, escrows) = setup(Expiry::Timestamp(0)).await;
+
+    let result = fill(&mut setup, &escrows, 0, u64::MAX).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowExpired);
+
+    let escrow_account = setup.context.banks_client.get_account(escrows[0].0).await.unwrap();
+    assert!(escrow_account.is_some(), "First Escrow should be left untouched");
+}
+
+// Trying to fill several Escrows for more mint B in total than the taker allows.
+
+#[tokio::test]
+async fn take_many_error_slippage_exceeded() {
+    let (mut setup, _, escrows) = setup(Expiry::Never).await;
+
+    let result = fill(&mut setup, &escrows, 0, 409).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::SlippageExceeded);
+}
+
+// Trying to fill an Escrow with the accounts of another maker.
+
+#[tokio::test]
+async fn take_many_error_escrow_account_mismatch() {
+    let (mut setup, bob, escrows) = setup(Expiry::Never).await;
+    let (escrow, _) = escrows[0];
+
+    let result = fill(&mut setup, &[(escrow, bob)], 0, u64::MAX).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::EscrowAccountMismatch);
+}
+
+// Trying to fill the same Escrow twice in one instruction.
+
+#[tokio::test]
+async fn take_many_error_duplicate_escrow() {
+    let (mut setup, _, escrows) = setup(Expiry::Never).await;
+    let first = escrows[0];
+
+    let result = fill(&mut setup, &[first, first], 0, u64::MAX).await;
+    assert!(result.is_err(), "A closed Escrow can't be filled again");
+
+    let escrow_account = setup.context.banks_client.get_account(first.0).await.unwrap();
+    assert!(escrow_account.is_some(), "Escrow should be left untouched");
+}
+
+// Trying to fill an Escrow with an allowlist, even one the taker is on: take_many
+// has no room for the Merkle proofs.
+
+#[tokio::test]
+async fn take_many_error_allowlist_escrow() {
+    let (mut setup, _, escrows) = setup(Expiry::Never).await;
+
+    // Restrict Bob's Escrow to the taker alone
+    let account = setup.context.banks_client.get_account(escrows[1].0).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut account.data.as_ref()).unwrap();
+    let allowlist = Some(merkle::leaf(setup.taker.pubkey().as_ref()));
+    set_escrow(&mut setup.context, &Escrow { allowlist, ..escrow });
+
+    let result = fill(&mut setup, &escrows, 0, u64::MAX).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::UnsupportedEscrow);
+}
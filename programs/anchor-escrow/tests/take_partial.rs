@@ -0,0 +1,315 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, Expiry}}, anchor_lang::AccountDeserialize, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Setting up an Escrow offering 100 tokens of mint A for 200 tokens of mint B
+// directly in the Bank, together with the mints and the token accounts.
+
+fn setup_escrow(test: &mut ProgramTest, maker: &Keypair, taker: &Keypair, mint_a: Pubkey, mint_b: Pubkey, seed: u64) -> Pubkey {
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b,
+        amount: 100,
+        receive: 200,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(test, mint_a, 100_000);
+    add_mint(test, mint_b, 100_000);
+    add_token_account(test, mint_b, taker.pubkey(), 100_000);
+    add_token_account(test, mint_a, escrow_pubkey, 100);
+
+    escrow_pubkey
+}
+
+// Filling the Escrow in two steps: the first fill releases mint A pro-rata and
+// keeps the Escrow open, the second one fills the rest and closes everything.
+
+#[tokio::test]
+async fn successful_take_partial() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let seed: u64 = rand::thread_rng().gen();
+    let escrow_pubkey = setup_escrow(&mut test, &maker, &taker, mint_a, mint_b, seed);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    // Fill a quarter of the Escrow
+    let mut transaction = Transaction::new_with_payer(
+        &[take_partial(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            50,
+            0,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let vault = spl_associated_token_account::get_associated_token_address(&escrow_pubkey, &mint_a);
+    let taker_mint_a = spl_associated_token_account::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_mint_b = spl_associated_token_account::get_associated_token_address(&maker.pubkey(), &mint_b);
+
+    assert_eq!(get_token_balance(&mut banks_client, vault).await.unwrap(), 75, "Vault should keep 75 tokens of mint A");
+    assert_eq!(get_token_balance(&mut banks_client, taker_mint_a).await.unwrap(), 25, "Taker should receive 25 tokens of mint A");
+    assert_eq!(get_token_balance(&mut banks_client, maker_mint_b).await.unwrap(), 50, "Maker should receive 50 tokens of mint B");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow.receive, 150, "Escrow should record the remaining receive");
+
+    // Fill the rest of the Escrow
+    let mut transaction = Transaction::new_with_payer(
+        &[take_partial(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            150,
+            0,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_token_balance(&mut banks_client, taker_mint_a).await.unwrap(), 100, "Taker should receive 100 tokens of mint A");
+    assert_eq!(get_token_balance(&mut banks_client, maker_mint_b).await.unwrap(), 200, "Maker should receive 200 tokens of mint B");
+
+    let vault_account = banks_client.get_account(vault).await.unwrap();
+    assert!(vault_account.is_none(), "Vault should be closed");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap();
+    assert!(escrow_account.is_none(), "Escrow account should be closed");
+}
+
+// Trying to pay more than the remaining receive of the Escrow.
+
+#[tokio::test]
+async fn take_partial_error_invalid_fill_amount() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let seed: u64 = rand::thread_rng().gen();
+    let escrow_pubkey = setup_escrow(&mut test, &maker, &taker, mint_a, mint_b, seed);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take_partial(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            201,
+            0,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidFillAmount);
+}
+
+// Filling part of an Escrow whose vault received extra tokens: the release stays
+// pro-rata to the recorded amount, and the extra tokens are swept on the last fill.
+
+#[tokio::test]
+async fn successful_take_partial_ignores_vault_donations() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(200_000);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let mint_b = Keypair::new().pubkey();
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(&mut test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b,
+        amount: 100,
+        receive: 200,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(&mut test, mint_a, 100_000);
+    add_mint(&mut test, mint_b, 100_000);
+    add_token_account(&mut test, mint_b, taker.pubkey(), 100_000);
+    let vault = add_token_account(&mut test, mint_a, escrow_pubkey, 1_100);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to taker
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take_partial(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            100,
+            50,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let taker_mint_a = spl_associated_token_account::get_associated_token_address(&taker.pubkey(), &mint_a);
+    assert_eq!(get_token_balance(&mut banks_client, taker_mint_a).await.unwrap(), 50, "Taker should receive 50 tokens of mint A");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow.amount, 50, "Escrow should record the remaining amount");
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take_partial(
+            anchor_escrow::id(),
+            spl_token::id(),
+            spl_token::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            100,
+            0,
+            u64::MAX,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_token_balance(&mut banks_client, taker_mint_a).await.unwrap(), 1_100, "Taker should sweep the vault on the last fill");
+    assert!(banks_client.get_account(vault).await.unwrap().is_none(), "Vault should be closed");
+}
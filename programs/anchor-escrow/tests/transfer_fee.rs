@@ -0,0 +1,266 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, ExpiryMode, TimeSpec}},
+    anchor_lang::AccountDeserialize,
+    helpers::{*, spl_token_2022_helpers::*},
+    rand::Rng,
+    solana_program_test::*,
+    solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    },
+    spl_associated_token_account::get_associated_token_address_with_program_id,
+};
+
+// Setting up a Token-2022 Escrow through the Make instruction, with a 1% transfer
+// fee on both mints: the maker deposits 10_000 tokens of mint A for 1_000 of mint B.
+
+async fn setup_escrow(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    maker: &Keypair,
+    taker: &Keypair,
+    net_receive: bool,
+) -> (Pubkey, Pubkey, Pubkey) {
+    let seed: u64 = rand::thread_rng().gen();
+
+    // Airdrop SOL to maker and taker
+    let _ = airdrop(banks_client, payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+    let _ = airdrop(banks_client, payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    // Create mints with a 1% transfer fee
+    let mint_a = create_mint_with_transfer_fee(banks_client, payer, 100, u64::MAX).await.unwrap();
+    let mint_b = create_mint_with_transfer_fee(banks_client, payer, 100, u64::MAX).await.unwrap();
+
+    // Create and mint tokens to maker's and taker's accounts
+    create_and_mint_to_token_2022_account(banks_client, mint_a, payer, maker.pubkey(), 10_000).await.unwrap();
+    create_and_mint_to_token_2022_account(banks_client, mint_b, payer, taker.pubkey(), 10_000).await.unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[make(
+            anchor_escrow::id(),
+            spl_token_2022::id(),
+            spl_token_2022::id(),
+            seed,
+            10_000,
+            1_000,
+            net_receive,
+            ExpiryMode::Timestamp(TimeSpec::After(3_600)),
+            None,
+            None,
+            0,
+            Default::default(),
+            None,
+            None,
+            None,
+            None,
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, maker], banks_client.get_latest_blockhash().await.unwrap());
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let (escrow_pubkey, _) = Pubkey::find_program_address(
+        &[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()],
+        &anchor_escrow::id(),
+    );
+
+    (escrow_pubkey, mint_a, mint_b)
+}
+
+// Making and taking an Escrow where receive is the gross amount sent by the taker:
+// the Escrow records what reached the vault and every leg pays its own transfer fee.
+
+#[tokio::test]
+async fn successful_take_with_transfer_fees() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(400_000);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, _) = test.start().await;
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let (escrow_pubkey, mint_a, mint_b) = setup_escrow(&mut banks_client, &payer, &maker, &taker, false).await;
+
+    // Verify the Escrow records the amount net of the transfer fee
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow.amount, 9_900, "Escrow should record the amount received by the vault");
+
+    let vault = get_associated_token_address_with_program_id(&escrow_pubkey, &mint_a, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, vault).await.unwrap(), 9_900);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[take(
+            anchor_escrow::id(),
+            spl_token_2022::id(),
+            spl_token_2022::id(),
+            taker.pubkey(),
+            maker.pubkey(),
+            mint_a,
+            mint_b,
+            escrow_pubkey,
+            fee_recipient,
+            None,
+            None,
+            None,
+            9_801,
+            1_000,
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &taker], banks_client.get_latest_blockhash().await.unwrap());
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let taker_ata_a = get_associated_token_address_with_program_id(&taker.pubkey(), &mint_a, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, taker_ata_a).await.unwrap(), 9_801, "Taker should receive mint A net of the transfer fee");
+
+    let maker_ata_b = get_associated_token_address_with_program_id(&maker.pubkey(), &mint_b, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, maker_ata_b).await.unwrap(), 990, "Maker should receive mint B net of the transfer fee");
+
+    // Verify accounts are closed despite the fees withheld in the vault
+    assert!(banks_client.get_account(vault).await.unwrap().is_none(), "Vault should be closed");
+    assert!(banks_client.get_account(escrow_pubkey).await.unwrap().is_none(), "Escrow account should be closed");
+}
+
+// Taking an Escrow where receive is net of transfer fees: the taker has to cover the
+// fee on top of receive, which their max_amount_b must allow for.
+
+#[tokio::test]
+async fn successful_take_with_net_receive() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(400_000);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, _) = test.start().await;
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let (escrow_pubkey, mint_a, mint_b) = setup_escrow(&mut banks_client, &payer, &maker, &taker, true).await;
+
+    let take_ix = |max_amount_b| take(
+        anchor_escrow::id(),
+        spl_token_2022::id(),
+        spl_token_2022::id(),
+        taker.pubkey(),
+        maker.pubkey(),
+        mint_a,
+        mint_b,
+        escrow_pubkey,
+        fee_recipient,
+        None,
+        None,
+        None,
+        0,
+        max_amount_b,
+        vec![],
+    );
+
+    // Paying only receive is not enough once the transfer fee is added
+    let mut transaction = Transaction::new_with_payer(&[take_ix(1_000)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &taker], banks_client.get_latest_blockhash().await.unwrap());
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::SlippageExceeded);
+
+    let mut transaction = Transaction::new_with_payer(&[take_ix(1_011)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &taker], banks_client.get_latest_blockhash().await.unwrap());
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let maker_ata_b = get_associated_token_address_with_program_id(&maker.pubkey(), &mint_b, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, maker_ata_b).await.unwrap(), 1_000, "Maker should receive exactly receive");
+
+    let taker_ata_b = get_associated_token_address_with_program_id(&taker.pubkey(), &mint_b, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, taker_ata_b).await.unwrap(), 10_000 - 1_011, "Taker should pay receive plus the transfer fee");
+}
+
+// Partially filling an Escrow where receive is net of transfer fees: the taker
+// covers the fee on their part, bounded by max_amount_b, and the maker receives the
+// part in full.
+
+#[tokio::test]
+async fn successful_take_partial_with_net_receive() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(400_000);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, _) = test.start().await;
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let (escrow_pubkey, mint_a, mint_b) = setup_escrow(&mut banks_client, &payer, &maker, &taker, true).await;
+
+    let take_partial_ix = |max_amount_b| take_partial(
+        anchor_escrow::id(),
+        spl_token_2022::id(),
+        spl_token_2022::id(),
+        taker.pubkey(),
+        maker.pubkey(),
+        mint_a,
+        mint_b,
+        escrow_pubkey,
+        fee_recipient,
+        None,
+        None,
+        None,
+        500,
+        0,
+        max_amount_b,
+        vec![],
+    );
+
+    // Paying only the part is not enough once the transfer fee is added
+    let mut transaction = Transaction::new_with_payer(&[take_partial_ix(500)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &taker], banks_client.get_latest_blockhash().await.unwrap());
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::SlippageExceeded);
+
+    let mut transaction = Transaction::new_with_payer(&[take_partial_ix(506)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &taker], banks_client.get_latest_blockhash().await.unwrap());
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let maker_ata_b = get_associated_token_address_with_program_id(&maker.pubkey(), &mint_b, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, maker_ata_b).await.unwrap(), 500, "Maker should receive exactly the part");
+
+    let taker_ata_b = get_associated_token_address_with_program_id(&taker.pubkey(), &mint_b, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, taker_ata_b).await.unwrap(), 10_000 - 506, "Taker should pay the part plus the transfer fee");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow.receive, 500, "Escrow should still ask for the rest");
+}
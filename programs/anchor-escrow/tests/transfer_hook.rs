@@ -0,0 +1,195 @@
+mod helpers;
+
+use {
+    anchor_escrow::state::{ExpiryMode, TimeSpec},
+    helpers::{*, spl_token_2022_helpers::*},
+    rand::Rng,
+    solana_program_test::*,
+    solana_sdk::{
+        account::Account as SolanaAccount,
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        instruction::AccountMeta,
+        native_token::LAMPORTS_PER_SOL,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    },
+    spl_associated_token_account::get_associated_token_address_with_program_id,
+    spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList},
+    spl_transfer_hook_interface::{
+        get_extra_account_metas_address,
+        instruction::{ExecuteInstruction, TransferHookInstruction},
+    },
+};
+
+// Mock transfer hook counting its executions in the single extra account it asks for.
+
+fn process_hook(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let TransferHookInstruction::Execute { .. } = TransferHookInstruction::unpack(data)? else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+
+    let mut counter = accounts[5].try_borrow_mut_data()?;
+    let count = u64::from_le_bytes(counter[..8].try_into().unwrap()) + 1;
+    counter[..8].copy_from_slice(&count.to_le_bytes());
+
+    Ok(())
+}
+
+// Registers the mock hook for a mint, with the counter as its extra account.
+
+fn add_hook_accounts(test: &mut ProgramTest, hook_program: Pubkey, mint: Pubkey, counter: Pubkey) {
+    let metas = [ExtraAccountMeta::new_with_pubkey(&counter, false, true).unwrap()];
+    let mut data = vec![0u8; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &metas).unwrap();
+
+    test.add_account(
+        get_extra_account_metas_address(&mint, &hook_program),
+        SolanaAccount {
+            lamports: u32::MAX as u64,
+            data,
+            owner: hook_program,
+            ..SolanaAccount::default()
+        },
+    );
+}
+
+async fn get_count(banks_client: &mut BanksClient, counter: Pubkey) -> u64 {
+    let account = banks_client.get_account(counter).await.unwrap().unwrap();
+    u64::from_le_bytes(account.data[..8].try_into().unwrap())
+}
+
+// Making and taking an Escrow between two Token-2022 mints with a transfer hook,
+// forwarding the hook accounts as remaining accounts.
+
+#[tokio::test]
+async fn successful_make_and_take_with_transfer_hook() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(400_000);
+
+    // Setup the mock hook program and its accounts
+    let hook_program = Pubkey::new_unique();
+    test.add_program("mock_transfer_hook", hook_program, processor!(process_hook));
+
+    let mint_a = Keypair::new();
+    let mint_b = Keypair::new();
+    let counter = Pubkey::new_unique();
+    test.add_account(
+        counter,
+        SolanaAccount {
+            lamports: u32::MAX as u64,
+            data: vec![0u8; 8],
+            owner: hook_program,
+            ..SolanaAccount::default()
+        },
+    );
+    add_hook_accounts(&mut test, hook_program, mint_a.pubkey(), counter);
+    add_hook_accounts(&mut test, hook_program, mint_b.pubkey(), counter);
+
+    // Setup config account without fees
+    let fee_recipient = Keypair::new().pubkey();
+    add_config(&mut test, fee_recipient, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let seed: u64 = rand::thread_rng().gen();
+
+    // Airdrop SOL to maker and taker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+    let _ = airdrop(&mut banks_client, &payer, &taker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    // Create mints and fund the maker and taker
+    let mint_a = create_mint_with_transfer_hook(&mut banks_client, &payer, &mint_a, hook_program).await.unwrap();
+    let mint_b = create_mint_with_transfer_hook(&mut banks_client, &payer, &mint_b, hook_program).await.unwrap();
+    create_and_mint_to_token_2022_account(&mut banks_client, mint_a, &payer, maker.pubkey(), 1_000).await.unwrap();
+    create_and_mint_to_token_2022_account(&mut banks_client, mint_b, &payer, taker.pubkey(), 1_000).await.unwrap();
+
+    let hook_accounts = vec![
+        AccountMeta::new_readonly(hook_program, false),
+        AccountMeta::new_readonly(get_extra_account_metas_address(&mint_a, &hook_program), false),
+        AccountMeta::new_readonly(get_extra_account_metas_address(&mint_b, &hook_program), false),
+        AccountMeta::new(counter, false),
+    ];
+
+    // Making without the hook accounts fails
+    let mut make_ix = make(
+        anchor_escrow::id(),
+        spl_token_2022::id(),
+        spl_token_2022::id(),
+        seed,
+        1_000,
+        500,
+        false,
+        ExpiryMode::Timestamp(TimeSpec::After(3_600)),
+        None,
+        None,
+        0,
+        Default::default(),
+        None,
+        None,
+        None,
+        None,
+        maker.pubkey(),
+        mint_a,
+        mint_b,
+    );
+
+    let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&make_ix), Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err(), "Make should fail without the hook accounts");
+
+    make_ix.accounts.extend(hook_accounts.clone());
+
+    let mut transaction = Transaction::new_with_payer(&[make_ix], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_count(&mut banks_client, counter).await, 1, "Hook should run on the deposit");
+
+    let (escrow_pubkey, _) = Pubkey::find_program_address(
+        &[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()],
+        &anchor_escrow::id(),
+    );
+
+    let mut take_ix = take(
+        anchor_escrow::id(),
+        spl_token_2022::id(),
+        spl_token_2022::id(),
+        taker.pubkey(),
+        maker.pubkey(),
+        mint_a,
+        mint_b,
+        escrow_pubkey,
+        fee_recipient,
+        None,
+        None,
+        None,
+        1_000,
+        500,
+        vec![],
+    );
+    take_ix.accounts.extend(hook_accounts);
+
+    let mut transaction = Transaction::new_with_payer(&[take_ix], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &taker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_count(&mut banks_client, counter).await, 3, "Hook should run on the payment and the withdrawal");
+
+    let taker_ata_a = get_associated_token_address_with_program_id(&taker.pubkey(), &mint_a, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, taker_ata_a).await.unwrap(), 1_000);
+
+    let maker_ata_b = get_associated_token_address_with_program_id(&maker.pubkey(), &mint_b, &spl_token_2022::id());
+    assert_eq!(get_token_2022_balance(&mut banks_client, maker_ata_b).await.unwrap(), 500);
+}
@@ -0,0 +1,212 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, Expiry, ExpiryMode, NftTerms, TimeSpec}}, anchor_lang::AccountDeserialize, helpers::*, rand::Rng, solana_program_test::*, solana_sdk::{
+        clock::Clock,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Setting up an open Escrow asking for 100 tokens of mint B directly in the Bank.
+
+fn setup_escrow(test: &mut ProgramTest, maker: &Keypair) -> Pubkey {
+    setup_escrow_with_nft(test, maker, Default::default())
+}
+
+fn setup_escrow_with_nft(test: &mut ProgramTest, maker: &Keypair, nft: NftTerms) -> Pubkey {
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    add_escrow(test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a: Keypair::new().pubkey(),
+        mint_b: Keypair::new().pubkey(),
+        amount: 100,
+        receive: 100,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft,
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    })
+}
+
+// Repricing an Escrow and moving its expiry, guarded by the expected current receive.
+
+#[tokio::test]
+async fn successful_update() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let escrow_pubkey = setup_escrow(&mut test, &maker);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update(
+            anchor_escrow::id(),
+            maker.pubkey(),
+            escrow_pubkey,
+            Some(150),
+            Some(ExpiryMode::Timestamp(TimeSpec::After(3_600))),
+            Some(100),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    assert!(result.result.is_ok());
+    assert!(emits_event_cpi(&result.metadata.unwrap().log_messages), "Update should emit its event through a CPI");
+
+    let clock = banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow.receive, 150);
+    assert_eq!(escrow.expiry, Expiry::Timestamp(clock.unix_timestamp + 3_600));
+}
+
+// Trying to reprice an Escrow whose receive changed since the maker last read it.
+
+#[tokio::test]
+async fn update_error_receive_mismatch() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let escrow_pubkey = setup_escrow(&mut test, &maker);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update(
+            anchor_escrow::id(),
+            maker.pubkey(),
+            escrow_pubkey,
+            Some(150),
+            None,
+            Some(90),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::ReceiveMismatch);
+}
+
+// Repricing an Escrow as set, returning the result of the update.
+
+async fn reprice(nft: NftTerms, receive: u64) -> Result<(), BanksClientError> {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let escrow_pubkey = setup_escrow_with_nft(&mut test, &maker, nft);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update(
+            anchor_escrow::id(),
+            maker.pubkey(),
+            escrow_pubkey,
+            Some(receive),
+            None,
+            None,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    banks_client.process_transaction(transaction).await
+}
+
+// Trying to reprice an Escrow to nothing.
+
+#[tokio::test]
+async fn update_error_zero_receive() {
+    let result = reprice(Default::default(), 0).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidAmount);
+}
+
+// Trying to reprice an Escrow asking for one NFT to several, while a collection
+// bid can ask for more NFTs.
+
+#[tokio::test]
+async fn update_error_nft_receive() {
+    let nft = NftTerms { nft_b: true, ..Default::default() };
+    let result = reprice(nft, 2).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidNftTerms);
+
+    let collection = NftTerms { nft_b: true, collection_b: true, ..Default::default() };
+    reprice(collection, 2).await.unwrap();
+}
+
+// Trying to move the expiry of a staged Escrow to its start time.
+
+#[tokio::test]
+async fn update_error_expiry_before_start() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let start_at = 1_000_000_000_000;
+    let escrow_pubkey = add_escrow(&mut test, &Escrow {
+        start_at,
+        ..default_escrow(maker.pubkey(), Keypair::new().pubkey(), Keypair::new().pubkey(), 100, 100)
+    });
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update(
+            anchor_escrow::id(),
+            maker.pubkey(),
+            escrow_pubkey,
+            None,
+            Some(ExpiryMode::Timestamp(TimeSpec::At(start_at))),
+            None,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidStartTime);
+}
@@ -0,0 +1,130 @@
+mod helpers;
+
+use {
+    anchor_escrow::{errors::EscrowErrors, state::{Escrow, Expiry}}, anchor_lang::AccountDeserialize, helpers::{*, spl_token_helpers::*}, rand::Rng, solana_program_test::*, solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    }
+};
+
+// Setting up an Escrow offering 100 tokens of mint A for 300 tokens of mint B
+// directly in the Bank.
+
+fn setup_escrow(test: &mut ProgramTest, maker: &Keypair, mint_a: Pubkey) -> Pubkey {
+    let seed: u64 = rand::thread_rng().gen();
+    let (_, bump) = Pubkey::find_program_address(&[b"escrow", maker.pubkey().as_ref(), seed.to_le_bytes().as_ref()], &anchor_escrow::id());
+
+    let escrow_pubkey = add_escrow(test, &Escrow {
+        seed,
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b: Keypair::new().pubkey(),
+        amount: 100,
+        receive: 300,
+        net_receive: false,
+        start_at: 0,
+        expiry: Expiry::Never,
+        taker: None,
+        allowlist: None,
+        nft: Default::default(),
+        auction: None,
+        english: None,
+        oracle: None,
+        bump,
+    });
+
+    add_mint(test, mint_a, 100_000);
+    add_token_account(test, mint_a, escrow_pubkey, 100);
+
+    escrow_pubkey
+}
+
+// Shrinking the vault of a live Escrow while keeping the same price.
+
+#[tokio::test]
+async fn successful_withdraw_some() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let escrow_pubkey = setup_escrow(&mut test, &maker, mint_a);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[withdraw_some(
+            anchor_escrow::id(),
+            spl_token::id(),
+            maker.pubkey(),
+            mint_a,
+            escrow_pubkey,
+            40,
+            true,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let vault = spl_associated_token_account::get_associated_token_address(&escrow_pubkey, &mint_a);
+    let maker_mint_a = spl_associated_token_account::get_associated_token_address(&maker.pubkey(), &mint_a);
+    assert_eq!(get_token_balance(&mut banks_client, vault).await.unwrap(), 60, "Vault should keep 60 tokens of mint A");
+    assert_eq!(get_token_balance(&mut banks_client, maker_mint_a).await.unwrap(), 40, "Maker should receive 40 tokens of mint A");
+
+    let escrow_account = banks_client.get_account(escrow_pubkey).await.unwrap().unwrap();
+    let escrow = Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow.receive, 180, "Receive should scale with the vault");
+}
+
+// Trying to empty the vault through withdraw_some instead of refund.
+
+#[tokio::test]
+async fn withdraw_some_error_invalid_amount() {
+    let mut test = ProgramTest::new(
+        "anchor_escrow",
+        anchor_escrow::id(),
+        None,
+    );
+
+    // Set compute unit limit
+    test.set_compute_max_units(100_000);
+
+    let maker = Keypair::new();
+    let mint_a = Keypair::new().pubkey();
+    let escrow_pubkey = setup_escrow(&mut test, &maker, mint_a);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // Airdrop SOL to maker
+    let _ = airdrop(&mut banks_client, &payer, &maker.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[withdraw_some(
+            anchor_escrow::id(),
+            spl_token::id(),
+            maker.pubkey(),
+            mint_a,
+            escrow_pubkey,
+            100,
+            false,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &maker], recent_blockhash);
+
+    let result = banks_client.process_transaction(transaction).await;
+    assert_escrow_error(result.unwrap_err(), EscrowErrors::InvalidAmount);
+}